@@ -1,5 +1,6 @@
-use declaration::Declaration;
+use declaration::{Assignment, Declaration};
 use version::Version;
+use template::Template;
 use crate::util::error::Error;
 use std::{
     io::{Read, BufReader},
@@ -12,15 +13,26 @@ pub mod value;
 pub mod types;
 pub mod expression;
 pub mod declaration;
+pub mod directive;
 pub mod parse;
 pub mod evaluate;
 pub mod tokenize;
-mod tree;
+mod arithmetic;
+pub mod function;
+mod table;
+mod parser;
+pub mod template;
+mod format;
 
 pub struct PeatCode {
     pub(crate) version: Version,
+    pub(crate) directives: Vec<Assignment>,
     pub(crate) declarations: Vec<Declaration>,
-    pub(crate) body: String
+    pub(crate) body: String,
+    pub(crate) template: Template,
+    pub(crate) file_name: String,
+    /// Line number in the file of the first line of the body.
+    pub(crate) body_line_number: usize,
 }
 
 pub(crate) fn get_peat_code(input_file_name: &Option<String>) -> Result<PeatCode, Error> {
@@ -29,6 +41,7 @@ pub(crate) fn get_peat_code(input_file_name: &Option<String>) -> Result<PeatCode
         None => Box::new(io::stdin())
     };
     let input_buf_reader = BufReader::new(source);
-    parse::parse_input(input_buf_reader)
+    let file_name = input_file_name.as_deref().unwrap_or("<stdin>");
+    parse::parse_input(input_buf_reader, file_name)
 }
 
@@ -0,0 +1,72 @@
+use std::fmt::{Display, Formatter};
+use std::fmt;
+use crate::code::tokenize::strings;
+use crate::util::error::Error;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+impl Operator {
+    /// Applies the operator, reporting overflow, underflow and division by zero as errors.
+    pub(crate) fn apply(self, lhs: u64, rhs: u64) -> Result<u64, Error> {
+        let result = match self {
+            Operator::Add => lhs.checked_add(rhs),
+            Operator::Subtract => lhs.checked_sub(rhs),
+            Operator::Multiply => lhs.checked_mul(rhs),
+            Operator::Divide => lhs.checked_div(rhs),
+            Operator::Modulo => lhs.checked_rem(rhs),
+        };
+        result.ok_or_else(|| {
+            let problem = match self {
+                Operator::Add | Operator::Multiply => "Integer overflow",
+                Operator::Subtract => "Integer underflow",
+                Operator::Divide | Operator::Modulo => "Division by zero"
+            };
+            Error::from(format!("{} in {} {} {}.", problem, lhs, self, rhs))
+        })
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Operator::Add => { f.write_str(strings::PLUS) }
+            Operator::Subtract => { f.write_str(strings::MINUS) }
+            Operator::Multiply => { f.write_str(strings::TIMES) }
+            Operator::Divide => { f.write_str(strings::INT_DIVIDE) }
+            Operator::Modulo => { f.write_str(strings::MODULO) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Operator;
+
+    fn error(operator: Operator, lhs: u64, rhs: u64) -> String {
+        operator.apply(lhs, rhs).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn results_in_range_are_exact() {
+        assert_eq!(Operator::Add.apply(u64::MAX - 1, 1).unwrap(), u64::MAX);
+        assert_eq!(Operator::Subtract.apply(3, 3).unwrap(), 0);
+        assert_eq!(Operator::Divide.apply(7, 2).unwrap(), 3);
+        assert_eq!(Operator::Modulo.apply(7, 2).unwrap(), 1);
+    }
+
+    #[test]
+    fn overflow_underflow_and_division_by_zero_are_errors() {
+        assert!(error(Operator::Add, u64::MAX, 1).contains("Integer overflow"));
+        assert!(error(Operator::Multiply, 1 << 32, 1 << 32).contains("Integer overflow"));
+        assert!(error(Operator::Subtract, 2, 3).contains("Integer underflow in 2 - 3."));
+        assert!(error(Operator::Divide, 1, 0).contains("Division by zero in 1 // 0."));
+        assert!(error(Operator::Modulo, 1, 0).contains("Division by zero"));
+    }
+}
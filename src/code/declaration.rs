@@ -1,4 +1,5 @@
-use crate::code::expression::{Expression, UIntRangeExpression};
+use crate::code::expression::Expression;
+use crate::code::tokenize::Token;
 use std::fmt::{Display, Formatter};
 use std::fmt;
 
@@ -9,7 +10,11 @@ pub(crate) struct Assignment {
 
 pub(crate) struct Iteration {
     pub(crate) id: String,
-    pub(crate) expression: Box<dyn UIntRangeExpression>
+    pub(crate) expression: Box<dyn Expression>
+}
+
+pub(crate) struct Input {
+    pub(crate) id: String
 }
 
 impl Assignment {
@@ -19,14 +24,31 @@ impl Assignment {
 }
 
 impl Iteration {
-    pub(crate) fn new(id: String, expression: Box<dyn UIntRangeExpression>) -> Iteration {
+    pub(crate) fn new(id: String, expression: Box<dyn Expression>) -> Iteration {
         Iteration { id, expression }
     }
 }
 
+impl Input {
+    pub(crate) fn new(id: String) -> Input {
+        Input { id }
+    }
+}
+
 pub(crate) enum Declaration {
     Assign(Assignment),
-    Iterate(Iteration)
+    Iterate(Iteration),
+    Input(Input)
+}
+
+impl Declaration {
+    pub(crate) fn id(&self) -> &str {
+        match self {
+            Declaration::Assign(assignment) => &assignment.id,
+            Declaration::Iterate(iteration) => &iteration.id,
+            Declaration::Input(input) => &input.id
+        }
+    }
 }
 
 impl Display for Declaration {
@@ -38,6 +60,9 @@ impl Display for Declaration {
             Declaration::Iterate(iteration) => {
                 format!("{} <- {}", iteration.id, iteration.expression).fmt(f)
             }
+            Declaration::Input(input) => {
+                format!("{} = {}", input.id, Token::Input).fmt(f)
+            }
         }
     }
 }
\ No newline at end of file
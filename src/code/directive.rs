@@ -0,0 +1,51 @@
+use crate::code::declaration::Assignment;
+use crate::code::types::Bindings;
+use crate::code::value::Value;
+use crate::util::duration;
+use crate::util::error::Error;
+use std::time::Duration;
+
+pub(crate) mod names {
+    pub(crate) const JOBS: &str = "JOBS";
+    pub(crate) const TIMEOUT: &str = "TIMEOUT";
+    pub(crate) const INTERPRETER: &str = "INTERPRETER";
+}
+
+pub(crate) fn is_directive(id: &str) -> bool {
+    matches!(id, names::JOBS | names::TIMEOUT | names::INTERPRETER)
+}
+
+pub(crate) struct Directives {
+    pub(crate) jobs: Option<u64>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) interpreter: Option<String>,
+}
+
+/// A duration is a string like "2h", or a number of seconds.
+fn as_duration(value: &Value) -> Result<Duration, Error> {
+    match value {
+        Value::UInt(secs) => duration::parse_duration(&secs.to_string()),
+        _ => duration::parse_duration(&value.as_string()?)
+    }
+}
+
+impl Directives {
+    pub(crate) fn evaluate(assignments: &[Assignment], bindings: &Bindings)
+                           -> Result<Directives, Error> {
+        let mut jobs: Option<u64> = None;
+        let mut timeout: Option<Duration> = None;
+        let mut interpreter: Option<String> = None;
+        for assignment in assignments {
+            let value = assignment.expression.eval(bindings)?;
+            match assignment.id.as_str() {
+                names::JOBS => { jobs = Some(value.as_int()?) }
+                names::TIMEOUT => { timeout = Some(as_duration(&value)?) }
+                names::INTERPRETER => { interpreter = Some(value.as_string()?) }
+                _ => {
+                    return Err(Error::from(format!("Unknown directive {}.", assignment.id)));
+                }
+            }
+        }
+        Ok(Directives { jobs, timeout, interpreter })
+    }
+}
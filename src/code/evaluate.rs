@@ -1,22 +1,299 @@
+use std::collections::HashMap;
 use crate::{
     util::error::Error,
     code::{
         types::{Bindings, BindingsIterator},
         PeatCode,
         declaration::{Declaration, Assignment, Iteration},
+        expression::AsTyped,
         types,
         value::Value
     }
 };
 
-pub(crate) fn evaluate_declarations(peat_code: &PeatCode) -> BindingsIterator {
-    let mut bindings_iter = types::new_bindings_iter();
-    for declaration in &peat_code.declarations {
-        bindings_iter = evaluate(declaration, bindings_iter);
+/// Values given on the command line replace the declaration of the same variable, and are
+/// added before all declarations if there is no such declaration.
+pub(crate) fn evaluate_declarations<'a>(peat_code: &'a PeatCode, overrides: &[(String, Value)])
+                                        -> BindingsIterator<'a> {
+    let (bindings, steps) = get_steps(peat_code, overrides);
+    let mut bindings_iter = types::new_bindings_iter(bindings);
+    for step in steps {
+        bindings_iter = match step {
+            Step::Override(id, value) => evaluate_override(id, value, bindings_iter),
+            Step::Declare(declaration) => evaluate(declaration, bindings_iter)
+        };
     }
     bindings_iter
 }
 
+/// Counting for progress reports gives up after visiting this many bindings, rather than delay
+/// the first job.
+pub(crate) const MAX_COUNTED_BINDINGS: u64 = 1_000_000;
+
+enum Step<'a> {
+    Override(String, Value),
+    Declare(&'a Declaration),
+}
+
+impl Step<'_> {
+    fn is_iteration(&self) -> bool {
+        matches!(self, Step::Declare(Declaration::Iterate(_)))
+    }
+
+    fn id(&self) -> &str {
+        match self {
+            Step::Override(id, _) => id,
+            Step::Declare(declaration) => declaration.id()
+        }
+    }
+
+    /// Whether the step is known to bind its variable to a string before it is evaluated.
+    fn is_str(&self) -> bool {
+        match self {
+            Step::Override(_, value) => matches!(value, Value::Str(_)),
+            Step::Declare(Declaration::Assign(assignment)) => {
+                matches!(assignment.expression.as_typed(), AsTyped::Str)
+            }
+            Step::Declare(Declaration::Iterate(_)) | Step::Declare(Declaration::Input(_)) => false
+        }
+    }
+
+    /// Names of the variables the value of the step depends on.
+    fn dependency_ids(&self) -> Vec<String> {
+        match self {
+            Step::Declare(Declaration::Assign(assignment)) => assignment.expression.ids(),
+            Step::Declare(Declaration::Iterate(iteration)) => iteration.expression.ids(),
+            Step::Override(..) | Step::Declare(Declaration::Input(_)) => Vec::new()
+        }
+    }
+
+    /// The values the step binds its variable to, which is one value unless it is an iteration.
+    fn values(&self, bindings: &Bindings) -> Result<Box<dyn Iterator<Item=Value>>, Error> {
+        match self {
+            Step::Override(_, value) => Ok(Box::new(std::iter::once(value.clone()))),
+            Step::Declare(Declaration::Assign(assignment)) => {
+                Ok(Box::new(std::iter::once(assignment.expression.eval(bindings)?)))
+            }
+            Step::Declare(Declaration::Iterate(iteration)) => {
+                iteration.expression.eval(bindings)?.into_iter_values()
+            }
+            Step::Declare(Declaration::Input(input)) => Err(missing_input_error(&input.id))
+        }
+    }
+}
+
+/// The bindings of values given on the command line for variables that are not declared, and
+/// a step for each declaration, which a value given on the command line may override.
+fn get_steps<'a>(peat_code: &'a PeatCode, overrides: &[(String, Value)])
+                 -> (Bindings, Vec<Step<'a>>) {
+    let injected: Vec<(String, Value)> =
+        overrides.iter().filter(|(id, _)| {
+            !peat_code.declarations.iter().any(|declaration| declaration.id() == id)
+        }).cloned().collect();
+    let steps =
+        peat_code.declarations.iter().map(|declaration| {
+            match overrides.iter().find(|(id, _)| declaration.id() == id) {
+                Some((id, value)) => Step::Override(id.clone(), value.clone()),
+                None => Step::Declare(declaration)
+            }
+        }).collect();
+    (types::bindings_from_entries(&injected), steps)
+}
+
+/// A step, and whether counting needs to go through its values one by one, which is when a
+/// later iteration depends on it.
+struct CountedStep<'a> {
+    step: Step<'a>,
+    is_walked: bool,
+}
+
+/// Marks the steps that later iterations depend on, directly or through assignments, and the
+/// step of the variable to count by, if any.
+fn counted_steps<'a>(steps: Vec<Step<'a>>, by_id: Option<&str>) -> Vec<CountedStep<'a>> {
+    let mut needed_ids: Vec<String> = by_id.map(String::from).into_iter().collect();
+    let mut counted_steps: Vec<CountedStep> =
+        steps.into_iter().rev().map(|step| {
+            let is_walked = needed_ids.iter().any(|id| id == step.id());
+            if is_walked || step.is_iteration() {
+                needed_ids.extend(step.dependency_ids());
+            }
+            CountedStep { step, is_walked }
+        }).collect();
+    counted_steps.reverse();
+    counted_steps
+}
+
+fn too_many_jobs_error() -> Error { Error::from("Number of jobs is too large to count.") }
+
+/// Numbers of jobs for each value of a variable, in the order the values first occur.
+struct CountsByValue {
+    counts: Vec<(String, u64)>,
+    indices: HashMap<String, usize>,
+}
+
+impl CountsByValue {
+    fn new() -> CountsByValue { CountsByValue { counts: Vec::new(), indices: HashMap::new() } }
+
+    fn add(&mut self, literal: String, n_jobs: u64) -> Result<(), Error> {
+        match self.indices.get(&literal) {
+            Some(&index) => {
+                let n_jobs_before = &mut self.counts[index].1;
+                *n_jobs_before = n_jobs_before.checked_add(n_jobs).ok_or_else(too_many_jobs_error)?;
+            }
+            None => {
+                self.indices.insert(literal.clone(), self.counts.len());
+                self.counts.push((literal, n_jobs));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counts the jobs that evaluate_declarations produces. An iteration that no later declaration
+/// depends on multiplies the count by the number of its values, so only iterations that later
+/// ones depend on are walked value by value. All other steps are still evaluated, once for each
+/// combination of the walked values, so that counting fails where running would, such as for a
+/// missing input.
+struct Counter {
+    n_bindings_left: u64,
+}
+
+impl Counter {
+    fn visit(&mut self) -> Result<(), Error> {
+        self.n_bindings_left =
+            self.n_bindings_left.checked_sub(1).ok_or_else(|| {
+                Error::from("Too many bindings to count the jobs.")
+            })?;
+        Ok(())
+    }
+
+    /// The number of values of a step that is not walked, and the bindings with its first value,
+    /// which stands in for all of them, so that the steps after it can be evaluated.
+    fn first_value(step: &Step, bindings: Bindings) -> Result<(u64, Bindings), Error> {
+        let (n_values, first) =
+            match step {
+                Step::Declare(Declaration::Iterate(iteration)) => {
+                    let value = iteration.expression.eval(&bindings)?;
+                    (value.n_iter_values()?, value.into_iter_values()?.next())
+                }
+                _ => (1, step.values(&bindings)?.next())
+            };
+        let bindings =
+            match first {
+                Some(first) => bindings.with_value(step.id().to_string(), first),
+                None => bindings
+            };
+        Ok((n_values, bindings))
+    }
+
+    fn count(&mut self, steps: &[CountedStep], bindings: Bindings) -> Result<u64, Error> {
+        let (counted, rest) = match steps.split_first() {
+            Some(split) => split,
+            None => return Ok(1)
+        };
+        let step = &counted.step;
+        if !counted.is_walked {
+            let (n_values, bindings) = Counter::first_value(step, bindings)?;
+            if n_values == 0 {
+                return Ok(0);
+            }
+            return n_values.checked_mul(self.count(rest, bindings)?)
+                .ok_or_else(too_many_jobs_error);
+        }
+        let mut n_jobs: u64 = 0;
+        for value in step.values(&bindings)? {
+            self.visit()?;
+            let n_jobs_for_value =
+                self.count(rest, bindings.clone().with_value(step.id().to_string(), value))?;
+            n_jobs = n_jobs.checked_add(n_jobs_for_value).ok_or_else(too_many_jobs_error)?;
+        }
+        Ok(n_jobs)
+    }
+
+    /// Adds up the jobs for each value of a variable, in the order the values first occur. Each
+    /// count is multiplied by the given factor, which accounts for the iterations before that
+    /// are not walked.
+    fn count_by(&mut self, steps: &[CountedStep], bindings: Bindings, id: &str, factor: u64,
+                counts: &mut CountsByValue) -> Result<(), Error> {
+        if let Some(value) = bindings.get(&id.to_string()) {
+            let literal = value.to_literal();
+            let n_jobs =
+                factor.checked_mul(self.count(steps, bindings)?).ok_or_else(too_many_jobs_error)?;
+            return counts.add(literal, n_jobs);
+        }
+        let (counted, rest) = match steps.split_first() {
+            Some(split) => split,
+            None => return Ok(())
+        };
+        let step = &counted.step;
+        if !counted.is_walked {
+            let (n_values, bindings) = Counter::first_value(step, bindings)?;
+            if n_values == 0 {
+                return Ok(());
+            }
+            let factor = factor.checked_mul(n_values).ok_or_else(too_many_jobs_error)?;
+            return self.count_by(rest, bindings, id, factor, counts);
+        }
+        for value in step.values(&bindings)? {
+            self.visit()?;
+            let value_bindings = bindings.clone().with_value(step.id().to_string(), value);
+            self.count_by(rest, value_bindings, id, factor, counts)?;
+        }
+        Ok(())
+    }
+}
+
+/// Names of all variables, in the order they are bound, starting with those that are given on the
+/// command line, but not declared.
+pub(crate) fn variable_ids(peat_code: &PeatCode, overrides: &[(String, Value)]) -> Vec<String> {
+    let (bindings, steps) = get_steps(peat_code, overrides);
+    let mut ids: Vec<String> = bindings.to_vec().into_iter().map(|(id, _)| id).collect();
+    ids.extend(steps.iter().map(|step| step.id().to_string()));
+    ids
+}
+
+/// Names of the variables that are known to be strings before evaluating anything.
+pub(crate) fn string_ids(peat_code: &PeatCode, overrides: &[(String, Value)]) -> Vec<String> {
+    let (bindings, steps) = get_steps(peat_code, overrides);
+    let mut ids: Vec<String> =
+        bindings.to_vec().into_iter().filter(|(_, value)| matches!(value, Value::Str(_)))
+            .map(|(id, _)| id).collect();
+    ids.extend(steps.iter().filter(|step| step.is_str()).map(|step| step.id().to_string()));
+    ids
+}
+
+/// Counts the jobs, visiting no more than the given number of bindings.
+pub(crate) fn count_jobs(peat_code: &PeatCode, overrides: &[(String, Value)],
+                         max_bindings: u64) -> Result<u64, Error> {
+    let (bindings, steps) = get_steps(peat_code, overrides);
+    Counter { n_bindings_left: max_bindings }.count(&counted_steps(steps, None), bindings)
+}
+
+/// Counts the jobs for each value of the given variable, such as a group index.
+pub(crate) fn count_jobs_by(peat_code: &PeatCode, overrides: &[(String, Value)], id: &str)
+                            -> Result<Vec<(String, u64)>, Error> {
+    let (bindings, steps) = get_steps(peat_code, overrides);
+    if bindings.get(&id.to_string()).is_none() && !steps.iter().any(|step| step.id() == id) {
+        return Err(Error::from(format!("Cannot count jobs by {}, since it is not declared.", id)));
+    }
+    let steps = counted_steps(steps, Some(id));
+    let mut counts = CountsByValue::new();
+    Counter { n_bindings_left: u64::MAX }.count_by(&steps, bindings, id, 1, &mut counts)?;
+    Ok(counts.counts)
+}
+
+fn missing_input_error(id: &str) -> Error {
+    Error::from(format!("Missing value for input {}. Provide it with -D {}=VALUE.", id, id))
+}
+
+fn evaluate_override<'a>(id: String, value: Value, bindings_iter: BindingsIterator<'a>)
+                         -> BindingsIterator<'a> {
+    let iter = bindings_iter.map(move |bindings_result| {
+        Ok(bindings_result?.with_value(id.clone(), value.clone()))
+    });
+    Box::new(iter)
+}
+
 fn bindings_for_assign(bindings_result: Result<Bindings, Error>,
                        assignment: &Assignment) -> Result<Bindings, Error> {
     let bindings = bindings_result?;
@@ -26,14 +303,14 @@ fn bindings_for_assign(bindings_result: Result<Bindings, Error>,
 }
 
 fn bindings_iter_for_iteration(bindings_result: Result<Bindings, Error>,
-                               iteration: &Iteration) -> BindingsIterator {
+                               iteration: &Iteration) -> BindingsIterator<'_> {
     let id = iteration.id.clone();
     match bindings_result {
         Ok(bindings) => {
-            match iteration.expression.eval_range(&bindings) {
-                Ok(range) => {
-                    let iter = range.to_range().map(move |i|{
-                        Ok(bindings.clone().with_value(id.clone(), Value::new_int(i)))
+            match iteration.expression.eval(&bindings).and_then(Value::into_iter_values) {
+                Ok(values) => {
+                    let iter = values.map(move |value| {
+                        Ok(bindings.clone().with_value(id.clone(), value))
                     });
                     Box::new(iter)
                 }
@@ -63,5 +340,95 @@ fn evaluate<'a>(declaration: &'a Declaration, bindings_iter: BindingsIterator<'a
             });
             Box::new(iter)
         }
+        Declaration::Input(input) => {
+            let iter = bindings_iter.map(move |bindings_result| {
+                bindings_result?;
+                Err(missing_input_error(&input.id))
+            });
+            Box::new(iter)
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::code::PeatCode;
+    use crate::code::parse::parse_input;
+    use crate::code::value::Value;
+    use super::{count_jobs, count_jobs_by, counted_steps, get_steps, string_ids};
+
+    fn parse_head(head: &str) -> PeatCode {
+        let input = format!("Peat 1.0\n{}===\necho\n", head);
+        parse_input(input.as_bytes(), "test.peat").unwrap()
+    }
+
+    #[test]
+    fn only_steps_that_later_iterations_depend_on_are_walked() {
+        let code = parse_head("A <- 0 .. 4\nN = A + 1\nB <- 0 .. N\nC <- 0 .. 10\nM = C\n");
+        let (_, steps) = get_steps(&code, &[]);
+        let is_walked: Vec<bool> =
+            counted_steps(steps, None).iter().map(|counted| counted.is_walked).collect();
+        assert_eq!(is_walked, vec![true, true, false, false, false]);
+        let (_, steps) = get_steps(&code, &[]);
+        let is_walked: Vec<bool> =
+            counted_steps(steps, Some("M")).iter().map(|counted| counted.is_walked).collect();
+        assert_eq!(is_walked, vec![true, true, false, true, true]);
+    }
+
+    #[test]
+    fn jobs_are_counted_across_dependent_and_independent_iterations() {
+        let code =
+            parse_head("A <- 0 .. 4\nN = A + 1\nB <- 0 .. N\nC <- 0 .. 100000\nD <- 0 .. 100000\n");
+        assert_eq!(count_jobs(&code, &[], u64::MAX).unwrap(), 100_000_000_000);
+        let counts = count_jobs_by(&code, &[], "A").unwrap();
+        let n_jobs: Vec<u64> = counts.iter().map(|(_, n_jobs)| *n_jobs).collect();
+        assert_eq!(n_jobs, vec![10_000_000_000, 20_000_000_000, 30_000_000_000, 40_000_000_000]);
+    }
+
+    #[test]
+    fn counts_by_value_add_up_repeated_values_in_first_order() {
+        let code = parse_head("X <- [\"b\", \"a\", \"b\"]\nI <- 0 .. 3\n");
+        let counts = count_jobs_by(&code, &[], "X").unwrap();
+        assert_eq!(counts, vec![(String::from("\"b\""), 6), (String::from("\"a\""), 3)]);
+    }
+
+    #[test]
+    fn counting_stops_at_the_limit_of_bindings() {
+        let code = parse_head("A <- 0 .. 1000\nB <- 0 .. A\n");
+        assert!(count_jobs(&code, &[], 100).is_err());
+        assert_eq!(count_jobs(&code, &[], 10_000).unwrap(), 499_500);
+    }
+
+    #[test]
+    fn counting_by_a_variable_fails_rather_than_overflows() {
+        let code = parse_head("X <- [1, 1]\nJ <- 0 .. 18446744073709551615\n");
+        assert!(count_jobs_by(&code, &[], "X").is_err());
+        assert!(count_jobs_by(&code, &[], "Y").is_err());
+    }
+
+    #[test]
+    fn strings_are_known_from_literals_and_values_given_on_the_command_line() {
+        let code =
+            parse_head("A = \"a\"\nB = A + 1\nC = 1\nD = env(\"X\", \"d\")\nF = \"f\" + B\n");
+        let overrides = [(String::from("C"), Value::new_str(String::from("c"))),
+                         (String::from("E"), Value::new_str(String::from("e")))];
+        assert_eq!(string_ids(&code, &overrides), vec!["E", "A", "C", "D", "F"]);
+    }
+
+    #[test]
+    fn errors_after_the_last_iteration_are_reported() {
+        for head in ["I <- 0 .. 3\nX = 1 // 0\n", "I <- 0 .. 3\nN = ?\n",
+                     "I <- 0 .. 3\nJ <- 0 .. 3\nX = J // 0\n"] {
+            assert!(count_jobs(&parse_head(head), &[], u64::MAX).is_err(), "{}", head);
+            assert!(count_jobs_by(&parse_head(head), &[], "I").is_err(), "{}", head);
+        }
+        let code = parse_head("I <- 0 .. 3\nJ <- 0 .. 5\nX = J + I\nY <- []\nZ = 1 // 0\n");
+        assert_eq!(count_jobs(&code, &[], u64::MAX).unwrap(), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn steps_after_iterations_that_are_not_walked_are_counted() {
+        let code = parse_head("I <- 0 .. 3\nJ <- 0 .. 5\nX = J + I\nY = ?\n");
+        let overrides = [(String::from("Y"), Value::new_int(1))];
+        assert_eq!(count_jobs(&code, &overrides, u64::MAX).unwrap(), 15);
+    }
+}
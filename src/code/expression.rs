@@ -1,26 +1,26 @@
 use std::fmt::{Display, Formatter};
 use std::fmt;
-use crate::code::value::{Value, UIntRange, UIntRangeRange};
+use crate::code::value::{Value, UIntRange};
 use crate::code::types::Bindings;
+use crate::code::tokenize;
+use crate::code::arithmetic::Operator;
 use crate::util::error::Error;
 
-pub(crate) enum Type {
-    UInt,
-    UIntRange,
-    UIntRangeRange,
-}
-
 pub(crate) trait Expression: Display {
     fn eval(&self, bindings: &Bindings) -> Result<Value, Error>;
-    fn get_type(&self) -> Type;
-    fn as_typed(&self) -> AsTyped;
+    fn as_typed(&self) -> AsTyped<'_>;
     fn clone_expr(&self) -> Box<dyn Expression>;
+    /// Names of the variables the expression refers to.
+    fn ids(&self) -> Vec<String>;
 }
 
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum AsTyped<'a> {
     UInt(&'a dyn UIntExpression),
     UIntRange(&'a dyn UIntRangeExpression),
     UIntRangeRange(&'a UIntRangeRangeExpression),
+    Str,
+    List,
 }
 
 impl AsTyped<'_> {
@@ -33,6 +33,10 @@ impl AsTyped<'_> {
                 Err(Error::from(
                     "Expected integer expression, but got range of ranges expression."
                 )),
+            AsTyped::Str =>
+                Err(Error::from("Expected integer expression, but got string expression.")),
+            AsTyped::List =>
+                Err(Error::from("Expected integer expression, but got list expression.")),
         }
     }
     pub(crate) fn as_range_expr(&self) -> Result<&dyn UIntRangeExpression, Error> {
@@ -45,6 +49,10 @@ impl AsTyped<'_> {
                 Err(Error::from(
                     "Expected range expression, but got range of ranges expression."
                 )),
+            AsTyped::Str =>
+                Err(Error::from("Expected range expression, but got string expression.")),
+            AsTyped::List =>
+                Err(Error::from("Expected range expression, but got list expression.")),
         }
     }
     pub(crate) fn as_range_range_expr(&self) -> Result<&UIntRangeRangeExpression, Error> {
@@ -54,7 +62,11 @@ impl AsTyped<'_> {
             AsTyped::UIntRange(_) =>
                 Err(Error::from("Expected range range expression, but got range expression.")),
             AsTyped::UIntRangeRange(range_range_expr) =>
-                Ok(*range_range_expr)
+                Ok(*range_range_expr),
+            AsTyped::Str =>
+                Err(Error::from("Expected range range expression, but got string expression.")),
+            AsTyped::List =>
+                Err(Error::from("Expected range range expression, but got list expression.")),
         }
     }
 }
@@ -82,8 +94,9 @@ pub(crate) struct UIntSimpleRangeExpression {
     until: Box<dyn UIntExpression>,
 }
 
+/// Divides a range or a list into groups.
 pub(crate) struct UIntRangeRangeExpression {
-    dividend: Box<dyn UIntRangeExpression>,
+    dividend: Box<dyn Expression>,
     divisor: Box<dyn UIntRangeExpression>,
 }
 
@@ -92,6 +105,31 @@ pub(crate) struct UIntPickRangeExpression {
     pick: Box<dyn UIntExpression>,
 }
 
+pub(crate) struct ListExpression {
+    items: Vec<Box<dyn Expression>>,
+}
+
+pub(crate) struct FieldExpression {
+    record: Box<dyn Expression>,
+    field: String,
+}
+
+pub(crate) struct StrLiteral {
+    value: String,
+}
+
+/// Integer arithmetic, where + also concatenates if one of the operands is a string.
+pub(crate) struct ArithmeticExpression {
+    operator: Operator,
+    lhs: Box<dyn Expression>,
+    rhs: Box<dyn Expression>,
+}
+
+/// An expression in parentheses.
+pub(crate) struct GroupExpression {
+    inner: Box<dyn Expression>,
+}
+
 impl UIntLiteral {
     pub(crate) fn new(value: u64) -> UIntLiteral { UIntLiteral { value } }
 }
@@ -108,14 +146,14 @@ impl UIntSimpleRangeExpression {
 }
 
 impl UIntRangeRangeExpression {
-    pub(crate) fn new(dividend: Box<dyn UIntRangeExpression>,
+    pub(crate) fn new(dividend: Box<dyn Expression>,
                       divisor: Box<dyn UIntRangeExpression>)
                       -> UIntRangeRangeExpression {
         UIntRangeRangeExpression { dividend, divisor }
     }
     pub(crate) fn clone_range_range_expr(&self) -> Box<UIntRangeRangeExpression> {
         Box::new(
-            UIntRangeRangeExpression::new(self.dividend.clone_range_expr(),
+            UIntRangeRangeExpression::new(self.dividend.clone_expr(),
                                           self.divisor.clone_range_expr())
         )
     }
@@ -129,11 +167,43 @@ impl UIntPickRangeExpression {
     }
 }
 
+impl ListExpression {
+    pub(crate) fn new(items: Vec<Box<dyn Expression>>) -> ListExpression {
+        ListExpression { items }
+    }
+}
+
+impl FieldExpression {
+    pub(crate) fn new(record: Box<dyn Expression>, field: String) -> FieldExpression {
+        FieldExpression { record, field }
+    }
+}
+
+impl StrLiteral {
+    pub(crate) fn new(value: String) -> StrLiteral { StrLiteral { value } }
+}
+
+impl ArithmeticExpression {
+    pub(crate) fn new(operator: Operator, lhs: Box<dyn Expression>, rhs: Box<dyn Expression>)
+                      -> ArithmeticExpression {
+        ArithmeticExpression { operator, lhs, rhs }
+    }
+    fn is_concat(&self) -> bool {
+        self.operator == Operator::Add &&
+            (matches!(self.lhs.as_typed(), AsTyped::Str) ||
+                matches!(self.rhs.as_typed(), AsTyped::Str))
+    }
+}
+
+impl GroupExpression {
+    pub(crate) fn new(inner: Box<dyn Expression>) -> GroupExpression { GroupExpression { inner } }
+}
+
 impl Expression for UIntLiteral {
     fn eval(&self, _: &Bindings) -> Result<Value, Error> { Ok(Value::UInt(self.value)) }
-    fn get_type(&self) -> Type { Type::UInt }
     fn as_typed<'a>(&'a self) -> AsTyped<'a> { AsTyped::UInt::<'a>(self) }
     fn clone_expr(&self) -> Box<dyn Expression> { Box::new(UIntLiteral { value: self.value }) }
+    fn ids(&self) -> Vec<String> { Vec::new() }
 }
 
 impl UIntExpression for UIntLiteral {
@@ -151,11 +221,11 @@ impl Expression for UIntVariable {
         }
     }
 
-    fn get_type(&self) -> Type { Type::UInt }
     fn as_typed<'a>(&'a self) -> AsTyped<'a> { AsTyped::UInt::<'a>(self) }
     fn clone_expr(&self) -> Box<dyn Expression> {
         Box::new(UIntVariable { id: self.id.clone() })
     }
+    fn ids(&self) -> Vec<String> { vec![self.id.clone()] }
 }
 
 impl UIntExpression for UIntVariable {
@@ -174,13 +244,12 @@ impl UIntExpression for UIntVariable {
 
 impl Expression for UIntSimpleRangeExpression {
     fn eval(&self, bindings: &Bindings) -> Result<Value, Error> {
-        let from = self.from.eval(bindings)?.as_int()?;
-        let until = self.until.eval(bindings)?.as_int()?;
+        let from = self.from.eval_int(bindings)?;
+        let until = self.until.eval_int(bindings)?;
         Ok(Value::new_range(from, until))
     }
 
-    fn get_type(&self) -> Type { Type::UIntRange }
-    fn as_typed(&self) -> AsTyped { AsTyped::UIntRange(self) }
+    fn as_typed(&self) -> AsTyped<'_> { AsTyped::UIntRange(self) }
 
     fn clone_expr(&self) -> Box<dyn Expression> {
         Box::new(
@@ -188,12 +257,14 @@ impl Expression for UIntSimpleRangeExpression {
                                            self.until.clone_int_expr())
         )
     }
+
+    fn ids(&self) -> Vec<String> { [self.from.ids(), self.until.ids()].concat() }
 }
 
 impl UIntRangeExpression for UIntSimpleRangeExpression {
     fn eval_range(&self, bindings: &Bindings) -> Result<UIntRange, Error> {
-        let from = self.from.eval(bindings)?.as_int()?;
-        let until = self.until.eval(bindings)?.as_int()?;
+        let from = self.from.eval_int(bindings)?;
+        let until = self.until.eval_int(bindings)?;
         Ok(UIntRange::new(from, until))
     }
     fn clone_range_expr(&self) -> Box<dyn UIntRangeExpression> {
@@ -206,41 +277,40 @@ impl UIntRangeExpression for UIntSimpleRangeExpression {
 
 impl Expression for UIntRangeRangeExpression {
     fn eval(&self, bindings: &Bindings) -> Result<Value, Error> {
-        let dividend = self.dividend.eval(bindings)?.as_range()?;
-        let divisor = self.divisor.eval(bindings)?.as_range()?;
-        Ok(Value::UIntRangeRange(UIntRangeRange::new(dividend, divisor)))
+        let divisor = self.divisor.eval_range(bindings)?;
+        self.dividend.eval(bindings)?.divide(divisor)
     }
 
-    fn get_type(&self) -> Type { Type::UIntRangeRange }
-    fn as_typed(&self) -> AsTyped { AsTyped::UIntRangeRange(self) }
+    fn as_typed(&self) -> AsTyped<'_> { AsTyped::UIntRangeRange(self) }
 
     fn clone_expr(&self) -> Box<dyn Expression> {
-        Box::new(UIntRangeRangeExpression::new(self.dividend.clone_range_expr(),
+        Box::new(UIntRangeRangeExpression::new(self.dividend.clone_expr(),
                                                self.divisor.clone_range_expr()))
     }
+
+    fn ids(&self) -> Vec<String> { [self.dividend.ids(), self.divisor.ids()].concat() }
 }
 
 impl Expression for UIntPickRangeExpression {
     fn eval(&self, bindings: &Bindings) -> Result<Value, Error> {
-        let groups = self.groups.eval(bindings)?.as_range_range()?;
+        let groups = self.groups.eval(bindings)?;
         let pick = self.pick.eval(bindings)?.as_int()?;
-        Ok(Value::UIntRange(groups.pick(pick)?))
+        groups.pick(pick)
     }
 
-    fn get_type(&self) -> Type { Type::UIntRange }
-    fn as_typed(&self) -> AsTyped { AsTyped::UIntRange(self) }
+    fn as_typed(&self) -> AsTyped<'_> { AsTyped::UIntRange(self) }
 
     fn clone_expr(&self) -> Box<dyn Expression> {
         Box::new(UIntPickRangeExpression::new(self.groups.clone_range_range_expr(),
                                               self.pick.clone_int_expr()))
     }
+
+    fn ids(&self) -> Vec<String> { [self.groups.ids(), self.pick.ids()].concat() }
 }
 
 impl UIntRangeExpression for UIntPickRangeExpression {
     fn eval_range(&self, bindings: &Bindings) -> Result<UIntRange, Error> {
-        let groups = self.groups.eval(bindings)?.as_range_range()?;
-        let pick = self.pick.eval(bindings)?.as_int()?;
-        groups.pick(pick)
+        self.eval(bindings)?.as_range()
     }
 
     fn clone_range_expr(&self) -> Box<dyn UIntRangeExpression> {
@@ -249,6 +319,81 @@ impl UIntRangeExpression for UIntPickRangeExpression {
     }
 }
 
+impl Expression for ListExpression {
+    fn eval(&self, bindings: &Bindings) -> Result<Value, Error> {
+        let mut values = Vec::<Value>::new();
+        for item in &self.items {
+            item.eval(bindings)?.push_to_list(&mut values)?;
+        }
+        Ok(Value::List(values))
+    }
+    fn as_typed(&self) -> AsTyped<'_> { AsTyped::List }
+    fn clone_expr(&self) -> Box<dyn Expression> {
+        Box::new(ListExpression::new(self.items.iter().map(|item| item.clone_expr()).collect()))
+    }
+    fn ids(&self) -> Vec<String> { self.items.iter().flat_map(|item| item.ids()).collect() }
+}
+
+impl Expression for FieldExpression {
+    fn eval(&self, bindings: &Bindings) -> Result<Value, Error> {
+        self.record.eval(bindings)?.get_field(&self.field)
+    }
+    fn as_typed(&self) -> AsTyped<'_> { AsTyped::Str }
+    fn clone_expr(&self) -> Box<dyn Expression> {
+        Box::new(FieldExpression::new(self.record.clone_expr(), self.field.clone()))
+    }
+    fn ids(&self) -> Vec<String> { self.record.ids() }
+}
+
+impl Expression for StrLiteral {
+    fn eval(&self, _: &Bindings) -> Result<Value, Error> {
+        Ok(Value::new_str(self.value.clone()))
+    }
+    fn as_typed(&self) -> AsTyped<'_> { AsTyped::Str }
+    fn clone_expr(&self) -> Box<dyn Expression> { Box::new(StrLiteral::new(self.value.clone())) }
+    fn ids(&self) -> Vec<String> { Vec::new() }
+}
+
+impl Expression for ArithmeticExpression {
+    fn eval(&self, bindings: &Bindings) -> Result<Value, Error> {
+        let lhs = self.lhs.eval(bindings)?;
+        let rhs = self.rhs.eval(bindings)?;
+        match (self.operator, &lhs, &rhs) {
+            (Operator::Add, Value::Str(_), _) | (Operator::Add, _, Value::Str(_)) => {
+                Ok(Value::new_str(format!("{}{}", lhs.as_concat_str()?, rhs.as_concat_str()?)))
+            }
+            _ => Ok(Value::UInt(self.operator.apply(lhs.as_int()?, rhs.as_int()?)?))
+        }
+    }
+    fn as_typed(&self) -> AsTyped<'_> {
+        if self.is_concat() { AsTyped::Str } else { AsTyped::UInt(self) }
+    }
+    fn clone_expr(&self) -> Box<dyn Expression> {
+        Box::new(ArithmeticExpression::new(self.operator, self.lhs.clone_expr(),
+                                           self.rhs.clone_expr()))
+    }
+    fn ids(&self) -> Vec<String> { [self.lhs.ids(), self.rhs.ids()].concat() }
+}
+
+impl UIntExpression for ArithmeticExpression {
+    fn eval_int(&self, bindings: &Bindings) -> Result<u64, Error> {
+        self.eval(bindings)?.as_int()
+    }
+    fn clone_int_expr(&self) -> Box<dyn UIntExpression> {
+        Box::new(ArithmeticExpression::new(self.operator, self.lhs.clone_expr(),
+                                           self.rhs.clone_expr()))
+    }
+}
+
+impl Expression for GroupExpression {
+    fn eval(&self, bindings: &Bindings) -> Result<Value, Error> { self.inner.eval(bindings) }
+    fn as_typed(&self) -> AsTyped<'_> { self.inner.as_typed() }
+    fn clone_expr(&self) -> Box<dyn Expression> {
+        Box::new(GroupExpression::new(self.inner.clone_expr()))
+    }
+    fn ids(&self) -> Vec<String> { self.inner.ids() }
+}
+
 impl Display for UIntLiteral {
     fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
         Display::fmt(&self.value, formatter)
@@ -277,4 +422,35 @@ impl Display for UIntPickRangeExpression {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         Display::fmt(format!("{} $ {}", self.groups, self.pick).as_str(), f)
     }
-}
\ No newline at end of file
+}
+
+impl Display for StrLiteral {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&tokenize::quote(&self.value))
+    }
+}
+
+impl Display for ArithmeticExpression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(format!("{} {} {}", self.lhs, self.operator, self.rhs).as_str(), f)
+    }
+}
+
+impl Display for GroupExpression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(format!("({})", self.inner).as_str(), f)
+    }
+}
+
+impl Display for ListExpression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let item_strings: Vec<String> = self.items.iter().map(|item| item.to_string()).collect();
+        Display::fmt(format!("[{}]", item_strings.join(", ")).as_str(), f)
+    }
+}
+
+impl Display for FieldExpression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(format!("{}.{}", self.record, self.field).as_str(), f)
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt::{Display, Formatter};
+use std::fmt;
+use crate::code::value::Value;
+use crate::util::error::Error;
+
+pub(crate) mod names {
+    pub(crate) const PAD: &str = "pad";
+    pub(crate) const LEFT: &str = "left";
+    pub(crate) const RIGHT: &str = "right";
+    pub(crate) const UPPER: &str = "upper";
+    pub(crate) const LOWER: &str = "lower";
+    pub(crate) const HEX: &str = "hex";
+}
+
+/// Formatting of a value in a placeholder, like `pad(5)` in `<:I|pad(5):>`.
+#[derive(Copy, Clone)]
+pub(crate) enum Filter {
+    /// Pads with zeros on the left to a width.
+    Pad(usize),
+    /// Pads with spaces on the right to a width.
+    Left(usize),
+    /// Pads with spaces on the left to a width.
+    Right(usize),
+    Upper,
+    Lower,
+    /// Writes an integer in lower case hexadecimal.
+    Hex,
+}
+
+/// Wider padding is rejected, since it is more likely a typo than wanted.
+const MAX_WIDTH: usize = 1024;
+
+fn pad_to(string: String, width: usize, fill: char, is_left: bool) -> String {
+    let n_fill = width.saturating_sub(string.chars().count());
+    let fill_string: String = std::iter::repeat_n(fill, n_fill).collect();
+    if is_left { fill_string + &string } else { string + &fill_string }
+}
+
+impl Filter {
+    /// Parses a filter like `pad(5)` or `upper`.
+    pub(crate) fn parse(string: &str) -> Result<Filter, Error> {
+        let string = string.trim();
+        let (name, arg) =
+            match string.strip_suffix(')').and_then(|rest| rest.split_once('(')) {
+                Some((name, arg)) => (name.trim(), Some(arg.trim())),
+                None => (string, None)
+            };
+        let width = || {
+            let width =
+                arg.and_then(|arg| arg.parse::<usize>().ok()).ok_or_else(|| {
+                    Error::from(format!("Format {} needs a width, like {}(5), but got {}.", name,
+                                        name, string))
+                })?;
+            if width > MAX_WIDTH {
+                return Err(Error::from(format!("Format {} can pad to at most {} characters, but \
+                got {}.", name, MAX_WIDTH, string)));
+            }
+            Ok(width)
+        };
+        let filter =
+            match name {
+                names::PAD => Filter::Pad(width()?),
+                names::LEFT => Filter::Left(width()?),
+                names::RIGHT => Filter::Right(width()?),
+                names::UPPER => Filter::Upper,
+                names::LOWER => Filter::Lower,
+                names::HEX => Filter::Hex,
+                _ => {
+                    return Err(Error::from(format!(
+                        "Unknown format {}. Known formats are {}(N), {}(N), {}(N), {}, {} and {}.",
+                        name, names::PAD, names::LEFT, names::RIGHT, names::UPPER, names::LOWER,
+                        names::HEX
+                    )));
+                }
+            };
+        let takes_width = matches!(filter, Filter::Pad(_) | Filter::Left(_) | Filter::Right(_));
+        if arg.is_some() && !takes_width {
+            return Err(Error::from(format!("Format {} takes no arguments, but got {}.", name,
+                                           string)));
+        }
+        Ok(filter)
+    }
+
+    pub(crate) fn apply(&self, value: Value) -> Result<Value, Error> {
+        let formatted =
+            match self {
+                Filter::Pad(width) => pad_to(value.to_string(), *width, '0', true),
+                Filter::Left(width) => pad_to(value.to_string(), *width, ' ', false),
+                Filter::Right(width) => pad_to(value.to_string(), *width, ' ', true),
+                Filter::Upper => value.to_string().to_uppercase(),
+                Filter::Lower => value.to_string().to_lowercase(),
+                Filter::Hex => {
+                    let ui = value.as_int().map_err(|_| {
+                        Error::from(format!("Format {} needs an integer, but got {}.", self,
+                                            value))
+                    })?;
+                    format!("{:x}", ui)
+                }
+            };
+        Ok(Value::Str(formatted))
+    }
+}
+
+/// Checks that hex is only applied to an integer, since all other formats give strings. The
+/// input is what the first format is applied to, and whether it is known to be a string.
+pub(crate) fn check_filters(filters: &[Filter], input: &str, is_str: bool) -> Result<(), Error> {
+    match filters.iter().position(|filter| matches!(filter, Filter::Hex)) {
+        Some(0) if is_str => {
+            Err(Error::from(format!("Format {} needs an integer, but {} is a string.", names::HEX,
+                                    input)))
+        }
+        Some(i_hex) if i_hex > 0 => {
+            Err(Error::from(format!("Format {} needs an integer, but format {} before it gives a \
+            string.", names::HEX, filters[i_hex - 1])))
+        }
+        _ => Ok(())
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::Pad(width) => write!(f, "{}({})", names::PAD, width),
+            Filter::Left(width) => write!(f, "{}({})", names::LEFT, width),
+            Filter::Right(width) => write!(f, "{}({})", names::RIGHT, width),
+            Filter::Upper => write!(f, "{}", names::UPPER),
+            Filter::Lower => write!(f, "{}", names::LOWER),
+            Filter::Hex => write!(f, "{}", names::HEX)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::code::value::Value;
+    use super::{check_filters, Filter};
+
+    fn apply(filters: &[&str], value: Value) -> String {
+        filters.iter().fold(value, |value, filter| {
+            Filter::parse(filter).unwrap().apply(value).unwrap()
+        }).to_string()
+    }
+
+    #[test]
+    fn filters_are_applied_in_order() {
+        assert_eq!(apply(&["pad(5)"], Value::UInt(42)), "00042");
+        assert_eq!(apply(&["hex", "upper", "pad(4)"], Value::UInt(42)), "002A");
+        assert_eq!(apply(&[" left( 4 ) ", "upper"], Value::new_str(String::from("ab"))), "AB  ");
+        assert_eq!(apply(&["right(4)"], Value::new_str(String::from("é"))), "   é");
+    }
+
+    #[test]
+    fn widths_do_not_truncate() {
+        assert_eq!(apply(&["pad(2)"], Value::UInt(12345)), "12345");
+    }
+
+    #[test]
+    fn malformed_filters_are_rejected() {
+        for string in ["pad", "pad()", "pad(x)", "pad(-1)", "pad(1025)", "upper(1)", "bold"] {
+            assert!(Filter::parse(string).is_err(), "{}", string);
+        }
+    }
+
+    #[test]
+    fn hex_needs_an_integer() {
+        let error = Filter::Hex.apply(Value::new_str(String::from("ff"))).err().unwrap();
+        assert!(error.to_string().contains("Format hex needs an integer"), "{}", error);
+    }
+
+    #[test]
+    fn hex_is_only_allowed_first_and_not_on_strings() {
+        let filters = [Filter::Hex, Filter::Upper];
+        assert!(check_filters(&filters, "I", false).is_ok());
+        let error = check_filters(&filters, "S", true).err().unwrap();
+        assert_eq!(error.to_string(), "Format hex needs an integer, but S is a string.");
+        let error = check_filters(&[Filter::Pad(4), Filter::Hex], "I", false).err().unwrap();
+        assert!(error.to_string().contains("format pad(4) before it gives a string"), "{}", error);
+    }
+}
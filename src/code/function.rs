@@ -0,0 +1,159 @@
+use std::fmt::{Display, Formatter};
+use std::{env, fmt};
+use crate::code::expression::{AsTyped, Expression, UIntExpression};
+use crate::code::table;
+use crate::code::types::Bindings;
+use crate::code::value::Value;
+use crate::util::error::Error;
+
+pub(crate) mod names {
+    pub(crate) const LINES: &str = "lines";
+    pub(crate) const ROWS: &str = "rows";
+    pub(crate) const COLUMN: &str = "column";
+    pub(crate) const ENV: &str = "env";
+}
+
+/// Minimum and maximum number of arguments.
+fn arity(name: &str) -> Option<(usize, usize)> {
+    match name {
+        names::LINES => Some((1, 1)),
+        names::ROWS => Some((1, 1)),
+        names::COLUMN => Some((2, 2)),
+        names::ENV => Some((1, 2)),
+        _ => None
+    }
+}
+
+pub(crate) struct FunctionCall {
+    name: String,
+    args: Vec<Box<dyn Expression>>,
+}
+
+impl FunctionCall {
+    pub(crate) fn new(name: String, args: Vec<Box<dyn Expression>>)
+                      -> Result<FunctionCall, Error> {
+        let (n_args_min, n_args_max) =
+            arity(&name).ok_or_else(|| Error::from(format!("Unknown function {}.", name)))?;
+        if args.len() < n_args_min || args.len() > n_args_max {
+            let n_args =
+                if n_args_min == n_args_max {
+                    n_args_min.to_string()
+                } else {
+                    format!("{} or {}", n_args_min, n_args_max)
+                };
+            return Err(Error::from(format!("Function {} takes {} arguments, but got {}.", name,
+                                           n_args, args.len())));
+        }
+        Ok(FunctionCall { name, args })
+    }
+
+    /// Whether this is known to read a string from the environment before it is evaluated,
+    /// which is when its default is a string literal or another string expression.
+    fn is_env_str(&self) -> bool {
+        self.name == names::ENV &&
+            matches!(self.args.get(1).map(|arg| arg.as_typed()), Some(AsTyped::Str))
+    }
+
+    /// Reads a string if the value of the default is a string, even if that is only known once
+    /// it is evaluated, such as for a variable bound to a string. Otherwise, it reads an
+    /// unsigned integer.
+    fn eval_env(&self, bindings: &Bindings) -> Result<Value, Error> {
+        let var = self.eval_str_arg(0, bindings)?;
+        let default = self.args.get(1).map(|default| default.eval(bindings)).transpose()?;
+        let string =
+            match env::var(&var) {
+                Ok(string) => string,
+                Err(env::VarError::NotPresent) => {
+                    return default.ok_or_else(|| Error::from(format!(
+                        "Environment variable {} is not set, and no default is given.", var
+                    )));
+                }
+                Err(env::VarError::NotUnicode(_)) => {
+                    return Err(Error::from(format!("Environment variable {} is not valid \
+                    Unicode.", var)));
+                }
+            };
+        if matches!(default, Some(Value::Str(_))) {
+            Ok(Value::Str(string))
+        } else {
+            string.trim().parse::<u64>().map(Value::UInt).map_err(|_| {
+                Error::from(format!("Environment variable {} needs to be an unsigned integer, \
+                but is \"{}\".", var, string))
+            })
+        }
+    }
+
+    fn eval_str_arg(&self, i: usize, bindings: &Bindings) -> Result<String, Error> {
+        self.args[i].eval(bindings)?.as_string()
+    }
+}
+
+impl Expression for FunctionCall {
+    fn eval(&self, bindings: &Bindings) -> Result<Value, Error> {
+        match self.name.as_str() {
+            names::LINES => Ok(Value::List(table::read_lines(&self.eval_str_arg(0, bindings)?)?)),
+            names::ROWS => Ok(Value::List(table::read_rows(&self.eval_str_arg(0, bindings)?)?)),
+            names::COLUMN => {
+                let path = self.eval_str_arg(0, bindings)?;
+                let column = self.eval_str_arg(1, bindings)?;
+                Ok(Value::List(table::read_column(&path, &column)?))
+            }
+            names::ENV => self.eval_env(bindings),
+            _ => Err(Error::from(format!("Unknown function {}.", self.name)))
+        }
+    }
+    fn as_typed(&self) -> AsTyped<'_> {
+        match self.name.as_str() {
+            names::ENV if self.is_env_str() => AsTyped::Str,
+            names::ENV => AsTyped::UInt(self),
+            _ => AsTyped::List
+        }
+    }
+    fn clone_expr(&self) -> Box<dyn Expression> {
+        let args = self.args.iter().map(|arg| arg.clone_expr()).collect();
+        Box::new(FunctionCall { name: self.name.clone(), args })
+    }
+    fn ids(&self) -> Vec<String> { self.args.iter().flat_map(|arg| arg.ids()).collect() }
+}
+
+impl UIntExpression for FunctionCall {
+    fn eval_int(&self, bindings: &Bindings) -> Result<u64, Error> {
+        self.eval(bindings)?.as_int()
+    }
+    fn clone_int_expr(&self) -> Box<dyn UIntExpression> {
+        let args = self.args.iter().map(|arg| arg.clone_expr()).collect();
+        Box::new(FunctionCall { name: self.name.clone(), args })
+    }
+}
+
+impl Display for FunctionCall {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let arg_strings: Vec<String> = self.args.iter().map(|arg| arg.to_string()).collect();
+        Display::fmt(format!("{}({})", self.name, arg_strings.join(", ")).as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use crate::code::parser::parse;
+    use crate::code::tokenize::Tokenizer;
+    use crate::code::types::bindings_from_entries;
+    use crate::code::value::Value;
+
+    fn eval(text: &str) -> String {
+        let tokens = Tokenizer::new(String::from(text)).write_to_vec().unwrap();
+        let default = Value::new_str(String::from("local"));
+        let bindings = bindings_from_entries(&[(String::from("D"), default)]);
+        parse(tokens).unwrap().eval(&bindings).unwrap().to_literal()
+    }
+
+    #[test]
+    fn env_reads_a_string_if_the_value_of_the_default_is_one() {
+        env::set_var("PEAT_TEST_ENV_STR", "007");
+        assert_eq!(eval("env(\"PEAT_TEST_ENV_STR\", D)"), "\"007\"");
+        assert_eq!(eval("env(\"PEAT_TEST_ENV_STR\", \"x\")"), "\"007\"");
+        assert_eq!(eval("env(\"PEAT_TEST_ENV_STR\", 1)"), "7");
+        assert_eq!(eval("env(\"PEAT_TEST_ENV_UNSET\", D)"), "\"local\"");
+    }
+}
@@ -1,19 +1,24 @@
-use std::io::{BufRead, BufReader, Lines, Read};
+use std::io::BufRead;
 
 use crate::{
     util::error::Error::PeatError,
     util::error::Error,
+    util::log::warning,
     code::{
         PeatCode,
         declaration::{
             Assignment,
             Declaration,
+            Input,
             Iteration
         },
-        expression::Expression,
-        tokenize::Token,
-        tokenize::Tokenizer,
-        tree,
+        directive,
+        template::Template,
+        expression::{AsTyped, Expression, StrLiteral},
+        tokenize::{Token, TokenAt, Tokenizer},
+        parser,
+        types,
+        value::Value,
         version::Version
     }
 };
@@ -28,7 +33,23 @@ fn parse_version_line(line: &str) -> Result<Version, Error> {
 }
 
 fn parse_expression(tokenizer: Tokenizer) -> Result<Box<dyn Expression>, Error> {
-    tree::reduce(tokenizer.write_to_vec()?)
+    parser::parse(tokenizer.write_to_vec()?)
+}
+
+/// Parses a command-line definition NAME=VALUE, where VALUE is a constant expression.
+pub(crate) fn parse_definition(definition: &str) -> Result<(String, Value), Error> {
+    let (id, value_str) =
+        definition.split_once('=').ok_or_else(|| PeatError(
+            format!("Definition needs to be of the form NAME=VALUE, but got {}.", definition)
+        ))?;
+    let id = id.trim();
+    match Tokenizer::new(String::from(id)).write_to_vec()?.as_slice() {
+        [TokenAt { token: Token::Id(_), .. }] => {}
+        _ => return Err(PeatError(format!("Invalid variable name {} in definition.", id)))
+    }
+    let expression = parse_expression(Tokenizer::new(String::from(value_str)))?;
+    let value = expression.eval(&types::get_empty_bindings())?;
+    Ok((String::from(id), value))
 }
 
 fn parse_declaration(decl_str: &str) -> Result<Declaration, Error> {
@@ -36,71 +57,159 @@ fn parse_declaration(decl_str: &str) -> Result<Declaration, Error> {
     let token1 =
         tokenizer.strip_token()?.ok_or_else(|| Error::from("Empty declaration"))?;
     let id =
-        if let Token::Id(id) = token1 {
+        if let Token::Id(id) = token1.token {
             id
         } else {
-            return Err(Error::from("Declaration needs to start with an identifier"));
+            return Err(Error::from("Declaration needs to start with an identifier")
+                .at(token1.span));
         };
     let token2 =
         tokenizer.strip_token()?.ok_or_else(|| Error::from("Missing '=' or '<-'."))?;
-    match token2 {
+    match token2.token {
         Token::Assign => {
-            let expression = parse_expression(tokenizer)?;
-            Ok(Declaration::Assign(Assignment::new(id, expression)))
+            let tokens = tokenizer.write_to_vec()?;
+            if let [TokenAt { token: Token::Input, .. }] = tokens.as_slice() {
+                Ok(Declaration::Input(Input::new(id)))
+            } else {
+                let expression = parser::parse(tokens)?;
+                Ok(Declaration::Assign(Assignment::new(id, expression)))
+            }
         }
         Token::Iterate => {
-            let expression =
-                parse_expression(tokenizer)?.as_typed().as_range_expr()?.clone_range_expr();
+            let expression = parse_expression(tokenizer)?;
+            match expression.as_typed() {
+                AsTyped::UIntRange(_) | AsTyped::List | AsTyped::UInt(_) => {}
+                _ => {
+                    return Err(PeatError(
+                        format!("Expected range or list to iterate over, but got {}.", expression)
+                    ));
+                }
+            }
             Ok(Declaration::Iterate(Iteration::new(id, expression)))
         }
-        _ => {
-            return Err(PeatError(
-                format!("Expected {} or {}, but got {}.", Token::Assign, Token::Iterate, token2)
-            ));
+        token => {
+            Err(PeatError(format!("Expected {} or {}, but got {}.", Token::Assign,
+                                  Token::Iterate, token)).at(token2.span))
         }
     }
 }
 
 const HEADER_END_LINE: &str = "===";
 
+/// A head line like `#! /bin/bash -eu` is short for `@INTERPRETER = "/bin/bash -eu"`.
+const INTERPRETER_LINE_START: &str = "#!";
+
+/// Starts a head line that sets a directive rather than a variable, like `@JOBS = 16`.
+pub(crate) const DIRECTIVE_LINE_START: &str = "@";
+
 fn is_header_end_line(line: &str) -> bool {
     line == HEADER_END_LINE
 }
 
-type InputLines = Lines<BufReader<Box<dyn Read>>>;
+/// Reads the head line by line, and then the body as it is.
+struct InputLines<R: BufRead> {
+    reader: R,
+    line_number: usize,
+}
+
+impl<R: BufRead> InputLines<R> {
+    fn new(reader: R) -> InputLines<R> { InputLines { reader, line_number: 0 } }
 
-fn read_next_line(lines: &mut InputLines) -> Result<String, Error> {
-    Ok(lines.next().ok_or_else(|| Error::from("File is incomplete."))??)
+    /// Reads the next line without its line ending, and its line number, counting from 1.
+    fn read_next_line(&mut self) -> Result<(usize, String), Error> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(Error::from("File is incomplete."));
+        }
+        self.line_number += 1;
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok((self.line_number, line))
+    }
+
+    fn read_rest(mut self) -> Result<String, Error> {
+        let mut rest = String::new();
+        self.reader.read_to_string(&mut rest)?;
+        Ok(rest)
+    }
+}
+
+/// Parses a line like `@JOBS = 16` as a directive. The prefix is replaced by a space, so that
+/// errors show the right columns.
+fn parse_directive(line: &str) -> Result<Assignment, Error> {
+    let declaration = parse_declaration(&line.replacen(DIRECTIVE_LINE_START, " ", 1))?;
+    if !directive::is_directive(declaration.id()) {
+        return Err(PeatError(format!(
+            "Unknown directive {}{}. Known directives are {}{}, {}{} and {}{}.",
+            DIRECTIVE_LINE_START, declaration.id(), DIRECTIVE_LINE_START, directive::names::JOBS,
+            DIRECTIVE_LINE_START, directive::names::TIMEOUT, DIRECTIVE_LINE_START,
+            directive::names::INTERPRETER
+        )));
+    }
+    match declaration {
+        Declaration::Assign(assignment) => Ok(assignment),
+        Declaration::Iterate(iteration) => {
+            Err(PeatError(format!("Cannot iterate directive {}{}.", DIRECTIVE_LINE_START,
+                                  iteration.id)))
+        }
+        Declaration::Input(input) => {
+            Err(PeatError(format!("Directive {}{} cannot be an input.", DIRECTIVE_LINE_START,
+                                  input.id)))
+        }
+    }
 }
 
-fn parse_declarations(lines: &mut InputLines)
-                      -> Result<Vec<Declaration>, Error> {
+fn parse_declarations<R: BufRead>(lines: &mut InputLines<R>, file_name: &str)
+                      -> Result<(Vec<Assignment>, Vec<Declaration>), Error> {
+    let mut directives = Vec::<Assignment>::new();
     let mut declarations = Vec::<Declaration>::new();
     loop {
-        let line = read_next_line(lines)?;
+        let (line_number, line) = lines.read_next_line()?;
         if is_header_end_line(&line) {
-            break Ok(declarations);
+            break Ok((directives, declarations));
+        }
+        if let Some(command) = line.strip_prefix(INTERPRETER_LINE_START) {
+            let expression = Box::new(StrLiteral::new(command.trim().to_string()));
+            directives.push(Assignment::new(directive::names::INTERPRETER.to_string(),
+                                            expression));
+            continue;
+        }
+        if line.trim_start().starts_with(DIRECTIVE_LINE_START) {
+            let directive =
+                parse_directive(&line)
+                    .map_err(|error| error.in_line(file_name, line_number, &line))?;
+            directives.push(directive);
+            continue;
+        }
+        let declaration =
+            parse_declaration(&line)
+                .map_err(|error| error.in_line(file_name, line_number, &line))?;
+        if directive::is_directive(declaration.id()) {
+            warning!("{}:{}: {} is a variable, not a directive. To set the directive, write {}{} \
+            instead.", file_name, line_number, declaration.id(), DIRECTIVE_LINE_START,
+                     declaration.id());
         }
-        let declaration = parse_declaration(&line)?;
         declarations.push(declaration);
     }
 }
 
-fn parse_body(lines: &mut InputLines) -> Result<String, Error> {
-    let mut body = String::new();
-    for line in lines {
-        let line = line?;
-        body.push_str(&line)
-    }
-    Ok(body)
-}
-
-pub(crate) fn parse_input(reader: BufReader<Box<dyn Read>>) -> Result<PeatCode, Error> {
-    let mut lines = reader.lines();
-    let version = parse_version_line(&read_next_line(&mut lines)?)?;
-    let declarations = parse_declarations(&mut lines)?;
-    let body = parse_body(&mut lines)?;
-    Ok(PeatCode { version, declarations, body })
+pub(crate) fn parse_input<R: BufRead>(reader: R, file_name: &str) -> Result<PeatCode, Error> {
+    let mut lines = InputLines::new(reader);
+    let (line_number, version_line) = lines.read_next_line()?;
+    let version =
+        parse_version_line(&version_line)
+            .map_err(|error| error.in_line(file_name, line_number, &version_line))?;
+    let (directives, declarations) = parse_declarations(&mut lines, file_name)?;
+    let body_line_number = lines.line_number + 1;
+    let body = lines.read_rest()?;
+    let template = Template::parse_in_file(&body, file_name, body_line_number)?;
+    let file_name = file_name.to_string();
+    Ok(PeatCode { version, directives, declarations, body, template, file_name,
+                  body_line_number })
 }
 
 // fn print_lines(file_path_opt: Option<String>) -> Result<(), String> {
@@ -112,4 +221,60 @@ pub(crate) fn parse_input(reader: BufReader<Box<dyn Read>>) -> Result<PeatCode,
 //         println!("{}", line?);
 //     }
 //     Ok(())
-// }
\ No newline at end of file
+// }
+#[cfg(test)]
+mod tests {
+    use super::parse_input;
+
+    fn parse_body(input: &str) -> String {
+        parse_input(input.as_bytes(), "test.peat").unwrap().body
+    }
+
+    #[test]
+    fn multi_line_body_is_kept() {
+        let input = "Peat 1.0\n===\necho one\necho two\necho three\n";
+        assert_eq!(parse_body(input), "echo one\necho two\necho three\n");
+    }
+
+    #[test]
+    fn missing_trailing_newline_is_kept() {
+        let input = "Peat 1.0\n===\necho one\necho two";
+        assert_eq!(parse_body(input), "echo one\necho two");
+    }
+
+    #[test]
+    fn blank_lines_and_indentation_are_kept() {
+        let input = "Peat 1.0\n===\n\n  echo one\n\n\techo two\n\n";
+        assert_eq!(parse_body(input), "\n  echo one\n\n\techo two\n\n");
+    }
+
+    #[test]
+    fn crlf_head_is_accepted_and_crlf_body_is_kept() {
+        let input = "Peat 1.0\r\nX = 1\r\n===\r\necho <:X:>\r\necho done\r\n";
+        let code = parse_input(input.as_bytes(), "test.peat").unwrap();
+        assert_eq!(code.declarations.len(), 1);
+        assert_eq!(code.body, "echo <:X:>\r\necho done\r\n");
+    }
+
+    #[test]
+    fn directives_start_with_an_at_sign() {
+        let input = "Peat 1.0\n@JOBS = 4\n  @TIMEOUT = \"2h\"\nJOBS = 2\n===\necho\n";
+        let code = parse_input(input.as_bytes(), "test.peat").unwrap();
+        let directive_ids: Vec<&str> =
+            code.directives.iter().map(|directive| directive.id.as_str()).collect();
+        assert_eq!(directive_ids, vec!["JOBS", "TIMEOUT"]);
+        let ids: Vec<&str> = code.declarations.iter().map(|declaration| declaration.id()).collect();
+        assert_eq!(ids, vec!["JOBS"]);
+    }
+
+    #[test]
+    fn unknown_and_iterated_directives_are_rejected() {
+        for (line, message) in [("@JOB = 4", "test.peat:2: Unknown directive @JOB."),
+                                ("@JOBS <- 0 .. 4", "Cannot iterate directive @JOBS."),
+                                ("@JOBS = 1 + + 2", "test.peat:2:13:")] {
+            let input = format!("Peat 1.0\n{}\n===\necho\n", line);
+            let error = parse_input(input.as_bytes(), "test.peat").err().unwrap();
+            assert!(error.to_string().contains(message), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use crate::code::tokenize::{Token, TokenAt};
+use crate::code::expression::{Expression, UIntRangeRangeExpression, UIntSimpleRangeExpression,
+                              UIntPickRangeExpression, UIntVariable, UIntLiteral, StrLiteral,
+                              ArithmeticExpression, GroupExpression, ListExpression,
+                              FieldExpression, AsTyped};
+use crate::code::arithmetic::Operator;
+use crate::code::function::FunctionCall;
+use crate::util::error::{Error, Span};
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Assoc {
+    Left,
+    /// Cannot be chained without parentheses, such as `0 .. 3 .. 5`.
+    None,
+}
+
+/// Precedence and associativity of binary operators, where higher precedence binds more tightly.
+fn binary_op(token: &Token) -> Option<(u8, Assoc)> {
+    match token {
+        Token::Pick => Some((1, Assoc::None)),
+        Token::Divide => Some((2, Assoc::None)),
+        Token::Range => Some((3, Assoc::None)),
+        Token::Plus | Token::Minus => Some((4, Assoc::Left)),
+        Token::Times | Token::IntDivide | Token::Modulo => Some((5, Assoc::Left)),
+        _ => None
+    }
+}
+
+fn error_at(span: Span, error: Error) -> Error { error.at(span) }
+
+pub(crate) fn parse(tokens: Vec<TokenAt>) -> Result<Box<dyn Expression>, Error> {
+    let mut parser = Parser { tokens, i: 0 };
+    let expression = parser.parse_binary(0)?;
+    match parser.peek() {
+        None => Ok(expression),
+        Some(token_at) => {
+            Err(error_at(token_at.span, Error::from(
+                format!("Unexpected {} after expression {}.", token_at.token, expression)
+            )))
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<TokenAt>,
+    i: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&TokenAt> { self.tokens.get(self.i) }
+
+    fn next(&mut self) -> Option<TokenAt> {
+        let token_at = self.tokens.get(self.i).cloned();
+        if token_at.is_some() {
+            self.i += 1;
+        }
+        token_at
+    }
+
+    fn is_next(&self, token: &Token) -> bool {
+        matches!(self.peek(), Some(token_at) if &token_at.token == token)
+    }
+
+    /// The column just after the last token, to report a missing token at the end.
+    fn end_span(&self) -> Span {
+        let col =
+            self.tokens.last().map(|token_at| token_at.span.col + token_at.span.len).unwrap_or(1);
+        Span::new(col, 1)
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), Error> {
+        match self.next() {
+            Some(token_at) if &token_at.token == token => Ok(()),
+            Some(token_at) => {
+                Err(error_at(token_at.span, Error::from(
+                    format!("Expected {}, but got {}.", token, token_at.token)
+                )))
+            }
+            None => {
+                Err(error_at(self.end_span(), Error::from(
+                    format!("Expected {}, but the expression ended.", token)
+                )))
+            }
+        }
+    }
+
+    fn peek_binary_op(&self, min_precedence: u8) -> Option<(Token, Span, u8, Assoc)> {
+        let token_at = self.peek()?;
+        let (precedence, assoc) = binary_op(&token_at.token)?;
+        if precedence >= min_precedence {
+            Some((token_at.token.clone(), token_at.span, precedence, assoc))
+        } else {
+            None
+        }
+    }
+
+    /// Parses binary operators with at least the given precedence by precedence climbing.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Box<dyn Expression>, Error> {
+        let mut lhs = self.parse_postfix()?;
+        while let Some((op, span, precedence, assoc)) = self.peek_binary_op(min_precedence) {
+            self.next();
+            let rhs = self.parse_binary(precedence + 1)?;
+            lhs = build_binary_expression(&op, lhs, rhs).map_err(|error| error_at(span, error))?;
+            if assoc == Assoc::None {
+                if let Some(token_at) = self.peek() {
+                    if binary_op(&token_at.token).map(|(p, _)| p) == Some(precedence) {
+                        return Err(error_at(token_at.span, Error::from(format!(
+                            "{} cannot follow {} without parentheses.", token_at.token, op
+                        ))));
+                    }
+                }
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn parse_postfix(&mut self) -> Result<Box<dyn Expression>, Error> {
+        let mut expression = self.parse_primary()?;
+        while self.is_next(&Token::Field) {
+            self.next();
+            match self.next() {
+                Some(TokenAt { token: Token::Id(field), .. }) => {
+                    expression = Box::new(FieldExpression::new(expression, field));
+                }
+                Some(token_at) => {
+                    return Err(error_at(token_at.span, Error::from(
+                        format!("Expected field name after {}, but got {}.", Token::Field,
+                                token_at.token)
+                    )));
+                }
+                None => {
+                    return Err(error_at(self.end_span(), Error::from(
+                        format!("Expected field name after {}.", Token::Field)
+                    )));
+                }
+            }
+        }
+        Ok(expression)
+    }
+
+    fn parse_primary(&mut self) -> Result<Box<dyn Expression>, Error> {
+        let token_at = match self.next() {
+            Some(token_at) => token_at,
+            None => {
+                return Err(error_at(self.end_span(),
+                                    Error::from("Expected expression, but it ended.")));
+            }
+        };
+        let span = token_at.span;
+        match token_at.token {
+            Token::UInt(ui) => Ok(Box::new(UIntLiteral::new(ui))),
+            Token::Str(string) => Ok(Box::new(StrLiteral::new(string))),
+            Token::Id(id) => {
+                if self.is_next(&Token::ParenStart) {
+                    self.next();
+                    let args = self.parse_items(&Token::ParenEnd)?;
+                    let call =
+                        FunctionCall::new(id, args)
+                            .map_err(|error| error_at(span, error))?;
+                    Ok(Box::new(call))
+                } else {
+                    Ok(Box::new(UIntVariable::new(id)))
+                }
+            }
+            Token::ParenStart => {
+                let inner = self.parse_binary(0)?;
+                self.expect(&Token::ParenEnd)?;
+                Ok(Box::new(GroupExpression::new(inner)))
+            }
+            Token::ListStart => {
+                let items = self.parse_items(&Token::ListEnd)?;
+                Ok(Box::new(ListExpression::new(items)))
+            }
+            token => {
+                Err(error_at(span, Error::from(format!("Expected expression, but got {}.", token))))
+            }
+        }
+    }
+
+    /// Parses comma-separated expressions up to and including the given closing token.
+    fn parse_items(&mut self, end: &Token) -> Result<Vec<Box<dyn Expression>>, Error> {
+        let mut items = Vec::<Box<dyn Expression>>::new();
+        if self.is_next(end) {
+            self.next();
+            return Ok(items);
+        }
+        loop {
+            items.push(self.parse_binary(0)?);
+            if self.is_next(&Token::Comma) {
+                self.next();
+            } else {
+                self.expect(end)?;
+                break Ok(items);
+            }
+        }
+    }
+}
+
+fn build_binary_expression(op: &Token, lhs: Box<dyn Expression>, rhs: Box<dyn Expression>)
+                           -> Result<Box<dyn Expression>, Error> {
+    match op {
+        Token::Plus => build_arithmetic_expression(Operator::Add, lhs, rhs),
+        Token::Minus => build_arithmetic_expression(Operator::Subtract, lhs, rhs),
+        Token::Times => build_arithmetic_expression(Operator::Multiply, lhs, rhs),
+        Token::IntDivide => build_arithmetic_expression(Operator::Divide, lhs, rhs),
+        Token::Modulo => build_arithmetic_expression(Operator::Modulo, lhs, rhs),
+        Token::Range => Ok(Box::new(build_range_expression(lhs.as_ref(), rhs.as_ref())?)),
+        Token::Divide => Ok(Box::new(build_divide_expression(lhs, rhs.as_ref())?)),
+        Token::Pick => Ok(Box::new(build_pick_expression(lhs.as_ref(), rhs.as_ref())?)),
+        _ => Err(Error::from(format!("{} is not a binary operator.", op)))
+    }
+}
+
+fn build_arithmetic_expression(operator: Operator, lhs: Box<dyn Expression>,
+                               rhs: Box<dyn Expression>) -> Result<Box<dyn Expression>, Error> {
+    for operand in [lhs.as_ref(), rhs.as_ref()] {
+        match (operator, operand.as_typed()) {
+            (Operator::Add, AsTyped::Str) => {}
+            (_, typed) => { typed.as_int_expr()?; }
+        }
+    }
+    Ok(Box::new(ArithmeticExpression::new(operator, lhs, rhs)))
+}
+
+fn build_range_expression(lhs: &dyn Expression, rhs: &dyn Expression)
+                          -> Result<UIntSimpleRangeExpression, Error> {
+    let from = lhs.as_typed().as_int_expr()?.clone_int_expr();
+    let until = rhs.as_typed().as_int_expr()?.clone_int_expr();
+    Ok(UIntSimpleRangeExpression::new(from, until))
+}
+
+fn build_divide_expression(lhs: Box<dyn Expression>, rhs: &dyn Expression)
+                           -> Result<UIntRangeRangeExpression, Error> {
+    match lhs.as_typed() {
+        AsTyped::UIntRange(_) | AsTyped::List | AsTyped::UInt(_) => {}
+        _ => {
+            return Err(Error::from(format!("Expected range or list to divide, but got {}.",
+                                           lhs)));
+        }
+    };
+    let divisor = rhs.as_typed().as_range_expr()?.clone_range_expr();
+    Ok(UIntRangeRangeExpression::new(lhs, divisor))
+}
+
+fn build_pick_expression(lhs: &dyn Expression, rhs: &dyn Expression)
+                         -> Result<UIntPickRangeExpression, Error> {
+    let groups = lhs.as_typed().as_range_range_expr()?.clone_range_range_expr();
+    let pick = rhs.as_typed().as_int_expr()?.clone_int_expr();
+    Ok(UIntPickRangeExpression::new(groups, pick))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::code::tokenize::Tokenizer;
+    use crate::code::types::get_empty_bindings;
+    use crate::util::error::Error;
+    use super::parse;
+
+    fn eval(text: &str) -> Result<String, Error> {
+        let expression = parse(Tokenizer::new(String::from(text)).write_to_vec()?)?;
+        Ok(expression.eval(&get_empty_bindings())?.to_literal())
+    }
+
+    #[test]
+    fn products_bind_more_tightly_than_sums() {
+        assert_eq!(eval("1 + 2 * 3").unwrap(), "7");
+        assert_eq!(eval("(1 + 2) * 3").unwrap(), "9");
+        assert_eq!(eval("7 - 2 * 3 % 4").unwrap(), "5");
+    }
+
+    #[test]
+    fn operators_of_equal_precedence_group_to_the_left() {
+        assert_eq!(eval("10 - 3 - 2").unwrap(), "5");
+        assert_eq!(eval("10 - (3 - 2)").unwrap(), "9");
+        assert_eq!(eval("20 // 5 // 2").unwrap(), "2");
+    }
+
+    #[test]
+    fn arithmetic_binds_more_tightly_than_ranges() {
+        assert_eq!(eval("1 + 1 .. 2 * 2").unwrap(), eval("2 .. 4").unwrap());
+    }
+
+    #[test]
+    fn ranges_cannot_be_chained_without_parentheses() {
+        assert!(eval("0 .. 3 .. 5").is_err());
+        assert!(eval("(1 + 2").is_err());
+    }
+}
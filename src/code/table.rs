@@ -0,0 +1,123 @@
+use std::fs;
+use crate::code::value::Value;
+use crate::util::error::Error;
+
+const QUOTE: char = '"';
+
+fn read_file(path: &str) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|error| {
+        Error::from(format!("Could not read {}: {}", path, error))
+    })
+}
+
+fn non_empty_lines(content: &str) -> impl Iterator<Item=(usize, &str)> {
+    content.lines().enumerate().filter(|(_, line)| { !line.trim().is_empty() })
+}
+
+/// Files ending in .csv are comma-separated, all others are tab-separated.
+fn delimiter_for(path: &str) -> char {
+    if path.to_lowercase().ends_with(".csv") { ',' } else { '\t' }
+}
+
+/// Splits a line into fields, where a field in double quotes may contain the delimiter, and a
+/// double quote inside quotes is written as two double quotes.
+fn split_fields(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::<String>::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            if ch == QUOTE {
+                if chars.peek() == Some(&QUOTE) {
+                    field.push(QUOTE);
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(ch);
+            }
+        } else if ch == QUOTE && field.is_empty() {
+            in_quotes = true;
+        } else if ch == delimiter {
+            fields.push(std::mem::take(&mut field));
+        } else {
+            field.push(ch);
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+fn read_table(path: &str) -> Result<Table, Error> {
+    let content = read_file(path)?;
+    let delimiter = delimiter_for(path);
+    let mut lines = non_empty_lines(&content);
+    let header =
+        match lines.next() {
+            Some((_, line)) => split_fields(line, delimiter),
+            None => return Err(Error::from(format!("File {} has no header line.", path)))
+        };
+    let mut rows = Vec::<Vec<String>>::new();
+    for (i_line, line) in lines {
+        let row = split_fields(line, delimiter);
+        if row.len() != header.len() {
+            return Err(Error::from(format!(
+                "Line {} of {} has {} fields, but the header has {}.", i_line + 1, path,
+                row.len(), header.len()
+            )));
+        }
+        rows.push(row);
+    }
+    Ok(Table { header, rows })
+}
+
+pub(crate) fn read_lines(path: &str) -> Result<Vec<Value>, Error> {
+    let content = read_file(path)?;
+    Ok(non_empty_lines(&content).map(|(_, line)| Value::new_str(String::from(line))).collect())
+}
+
+pub(crate) fn read_rows(path: &str) -> Result<Vec<Value>, Error> {
+    let table = read_table(path)?;
+    let header = table.header;
+    let records =
+        table.rows.into_iter().map(|row| {
+            let fields =
+                header.iter().cloned().zip(row.into_iter().map(Value::new_str)).collect();
+            Value::Record(fields)
+        }).collect();
+    Ok(records)
+}
+
+pub(crate) fn read_column(path: &str, column: &str) -> Result<Vec<Value>, Error> {
+    let table = read_table(path)?;
+    let i_column =
+        table.header.iter().position(|name| name == column)
+            .ok_or_else(|| Error::from(format!("No column {} in {}.", column, path)))?;
+    Ok(table.rows.into_iter().map(|mut row| Value::new_str(row.swap_remove(i_column))).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{delimiter_for, split_fields};
+
+    #[test]
+    fn quoted_fields_may_contain_the_delimiter_and_quotes() {
+        assert_eq!(split_fields(r#"a,"b,c","say ""hi""""#, ','), vec!["a", "b,c", r#"say "hi""#]);
+        assert_eq!(split_fields("a\t\"b\tc\"\t", '\t'), vec!["a", "b\tc", ""]);
+        assert_eq!(split_fields(r#"x"y,"""#, ','), vec![r#"x"y"#, ""]);
+        assert_eq!(split_fields("", ','), vec![""]);
+    }
+
+    #[test]
+    fn only_csv_files_are_comma_separated() {
+        assert_eq!(delimiter_for("data.CSV"), ',');
+        assert_eq!(delimiter_for("data.tsv"), '\t');
+    }
+}
@@ -0,0 +1,256 @@
+use crate::code::format::{self, Filter};
+use crate::code::tokenize;
+use crate::code::types::Bindings;
+use crate::util::error::{Error, Span};
+
+pub(crate) mod delims {
+    pub(crate) const PRE: &str = "<:";
+    pub(crate) const POST: &str = ":>";
+    /// Stands for a literal `<:` in the body.
+    pub(crate) const ESCAPE: &str = "<::>";
+}
+
+/// A placeholder like `<:I:>`, `<:SAMPLE.reads:>` or `<:I|pad(5):>`.
+struct Placeholder {
+    id: String,
+    field: Option<String>,
+    filters: Vec<Filter>,
+    /// Where it is in the source, counting lines from 0.
+    line_index: usize,
+    span: Span,
+}
+
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A body or other text with placeholders, split into segments once, so that filling in the
+/// values for a job takes a single pass, and values are never themselves searched for
+/// placeholders.
+pub(crate) struct Template {
+    segments: Vec<Segment>,
+}
+
+fn col_of(line: &str, i: usize) -> usize { line[..i].chars().count() + 1 }
+
+fn is_valid_id(id: &str) -> bool {
+    let mut chars = id.chars();
+    matches!(chars.next(), Some(ch) if tokenize::is_valid_id_start(ch)) &&
+        chars.all(tokenize::is_valid_id_part)
+}
+
+fn line_without_cr(line: &str) -> &str { line.strip_suffix('\r').unwrap_or(line) }
+
+/// Adds to the last segment if it is a literal, since there is no need for two in a row.
+fn push_literal(segments: &mut Vec<Segment>, string: &str) {
+    match segments.last_mut() {
+        _ if string.is_empty() => {}
+        Some(Segment::Literal(literal)) => literal.push_str(string),
+        _ => segments.push(Segment::Literal(string.to_string()))
+    }
+}
+
+/// Adds the segments of a line of the template.
+fn parse_line(line: &str, line_index: usize, segments: &mut Vec<Segment>)
+              -> Result<(), Error> {
+    let mut i_rest = 0;
+    while let Some(i_found) = line[i_rest..].find(delims::PRE) {
+        let i_start = i_rest + i_found;
+        push_literal(segments, &line[i_rest..i_start]);
+        if line[i_start..].starts_with(delims::ESCAPE) {
+            push_literal(segments, delims::PRE);
+            i_rest = i_start + delims::ESCAPE.len();
+            continue;
+        }
+        let i_content = i_start + delims::PRE.len();
+        let content_len = line[i_content..].find(delims::POST).ok_or_else(|| {
+            Error::from(format!("Placeholder is not closed with {}. For a literal {}, write {}.",
+                                delims::POST, delims::PRE, delims::ESCAPE))
+                .at(Span::new(col_of(line, i_start), delims::PRE.len()))
+        })?;
+        let i_end = i_content + content_len + delims::POST.len();
+        let span = Span::new(col_of(line, i_start), line[i_start..i_end].chars().count());
+        let mut parts = line[i_content..(i_content + content_len)].split('|');
+        let content = parts.next().unwrap_or("").trim();
+        let filters =
+            parts.map(Filter::parse).collect::<Result<Vec<Filter>, Error>>()
+                .map_err(|error| error.at(span))?;
+        let (id, field) = match content.split_once('.') {
+            Some((id, field)) => (id, Some(field)),
+            None => (content, None)
+        };
+        if !is_valid_id(id) || field == Some("") {
+            return Err(Error::from(format!(
+                "Expected placeholder like {}I{} or {}SAMPLE.reads{}, but got {}. For a literal \
+                {}, write {}.", delims::PRE, delims::POST, delims::PRE, delims::POST,
+                &line[i_start..i_end], delims::PRE, delims::ESCAPE
+            )).at(span));
+        }
+        // Fields of records are read from tables, so they are strings.
+        format::check_filters(&filters, content, field.is_some()).map_err(|error| error.at(span))?;
+        let id = id.to_string();
+        let field = field.map(String::from);
+        segments.push(Segment::Placeholder(Placeholder { id, field, filters, line_index, span }));
+        i_rest = i_end;
+    }
+    push_literal(segments, &line[i_rest..]);
+    Ok(())
+}
+
+impl Template {
+    /// Parses a template from a file, such as the body, so that errors tell the file, line and
+    /// column.
+    pub(crate) fn parse_in_file(text: &str, file_name: &str, first_line_number: usize)
+                                -> Result<Template, Error> {
+        let mut segments = Vec::<Segment>::new();
+        for (line_index, line) in text.split('\n').enumerate() {
+            if line_index > 0 {
+                push_literal(&mut segments, "\n");
+            }
+            parse_line(line, line_index, &mut segments).map_err(|error| {
+                error.in_line(file_name, first_line_number + line_index, line_without_cr(line))
+            })?;
+        }
+        Ok(Template { segments })
+    }
+
+    fn placeholders(&self) -> impl Iterator<Item=&Placeholder> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Placeholder(placeholder) => Some(placeholder),
+            Segment::Literal(_) => None
+        })
+    }
+
+    /// Checks that each placeholder refers to one of the given variables, so that a typo fails
+    /// with its line and column rather than end up in the script, and that no placeholder formats
+    /// a variable that is known to be a string as hex. The text is what the template was parsed
+    /// from, to show the line.
+    pub(crate) fn check_ids(&self, ids: &[String], str_ids: &[String], text: &str,
+                            file_name: &str, first_line_number: usize) -> Result<(), Error> {
+        for placeholder in self.placeholders() {
+            let result =
+                if ids.contains(&placeholder.id) {
+                    format::check_filters(&placeholder.filters, &placeholder.id,
+                                          str_ids.contains(&placeholder.id))
+                } else {
+                    let known =
+                        if ids.is_empty() { String::from("none") } else { ids.join(", ") };
+                    Err(Error::from(format!(
+                        "Unknown variable {} in placeholder. Known variables: {}.",
+                        placeholder.id, known
+                    )))
+                };
+            if let Err(error) = result {
+                let line = text.split('\n').nth(placeholder.line_index).unwrap_or("");
+                return Err(error.at(placeholder.span).in_line(
+                    file_name, first_line_number + placeholder.line_index, line_without_cr(line)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fills in the values of the placeholders.
+    pub(crate) fn render(&self, bindings: &Bindings) -> Result<String, Error> {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => rendered.push_str(literal),
+                Segment::Placeholder(placeholder) => {
+                    let value = bindings.get(&placeholder.id).ok_or_else(|| {
+                        Error::from(format!("Unknown variable {} in placeholder.",
+                                            placeholder.id))
+                    })?;
+                    let mut value =
+                        match &placeholder.field {
+                            Some(field) => value.get_field(field)?,
+                            None => value
+                        };
+                    for filter in &placeholder.filters {
+                        value = filter.apply(value)?;
+                    }
+                    rendered.push_str(&value.to_string());
+                }
+            }
+        }
+        Ok(rendered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::code::types::bindings_from_entries;
+    use crate::code::value::Value;
+    use crate::util::error::Error;
+    use super::Template;
+
+    fn render(text: &str, entries: &[(&str, Value)]) -> Result<String, Error> {
+        let entries: Vec<(String, Value)> =
+            entries.iter().map(|(id, value)| (id.to_string(), value.clone())).collect();
+        Template::parse_in_file(text, "test.peat", 3)?.render(&bindings_from_entries(&entries))
+    }
+
+    #[test]
+    fn here_doc_survives_substitution() {
+        let text = "cat > out.txt <<EOF\nfirst <:X:>\n  second\nEOF\n";
+        assert_eq!(render(text, &[("X", Value::UInt(7))]).unwrap(),
+                   "cat > out.txt <<EOF\nfirst 7\n  second\nEOF\n");
+    }
+
+    #[test]
+    fn if_block_survives_substitution() {
+        let text = "if [ \"<:S:>\" = \"a\" ]; then\n  echo yes\nelse\n  echo no\nfi\n";
+        assert_eq!(render(text, &[("S", Value::new_str(String::from("a")))]).unwrap(),
+                   "if [ \"a\" = \"a\" ]; then\n  echo yes\nelse\n  echo no\nfi\n");
+    }
+
+    #[test]
+    fn substituted_values_are_not_substituted_again() {
+        let entries = [("X", Value::new_str(String::from("<:Y:>"))), ("Y", Value::UInt(1))];
+        assert_eq!(render("echo <:X:> <:Y:>\n", &entries).unwrap(), "echo <:Y:> 1\n");
+    }
+
+    #[test]
+    fn escaped_placeholder_is_kept_literally() {
+        let text = "echo \"<::>X:> is <:X:>\"\r\n";
+        assert_eq!(render(text, &[("X", Value::UInt(1))]).unwrap(), "echo \"<:X:> is 1\"\r\n");
+    }
+
+    #[test]
+    fn fields_and_formats_are_applied_in_placeholders() {
+        let record = Value::Record(vec![(String::from("n"), Value::UInt(42))]);
+        assert_eq!(render("<:R.n|pad(4):>", &[("R", record)]).unwrap(), "0042");
+    }
+
+    #[test]
+    fn malformed_placeholders_tell_line_and_column() {
+        let error = Template::parse_in_file("echo\necho <:X\n", "test.peat", 3).err().unwrap();
+        assert!(error.to_string().contains("test.peat:4:6:"), "{}", error);
+        let error = Template::parse_in_file("echo <:1X:>", "test.peat", 3).err().unwrap();
+        assert!(error.to_string().contains("Expected placeholder"), "{}", error);
+    }
+
+    #[test]
+    fn unknown_variables_are_found_before_rendering() {
+        let text = "echo <:X:>\necho <:Y:>\n";
+        let template = Template::parse_in_file(text, "test.peat", 3).unwrap();
+        let error =
+            template.check_ids(&[String::from("X")], &[], text, "test.peat", 3).err().unwrap();
+        assert!(error.to_string().contains("test.peat:4:6:"), "{}", error);
+        assert!(error.to_string().contains("Unknown variable Y"), "{}", error);
+    }
+
+    #[test]
+    fn hex_of_strings_is_found_before_rendering() {
+        let error = Template::parse_in_file("<:R.n|hex:>", "test.peat", 3).err().unwrap();
+        assert!(error.to_string().contains("but R.n is a string"), "{}", error);
+        let text = "echo <:I|hex:>\necho <:S|hex:>\n";
+        let template = Template::parse_in_file(text, "test.peat", 3).unwrap();
+        let ids = [String::from("I"), String::from("S")];
+        let error =
+            template.check_ids(&ids, &[String::from("S")], text, "test.peat", 3).err().unwrap();
+        assert!(error.to_string().contains("test.peat:4:6: Format hex needs an integer, but S is \
+        a string."), "{}", error);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::util::error::Error;
 use crate::util::error::Error::PeatError;
+use crate::util::error::Span;
 use std::fmt::{Display, Formatter};
 use std::fmt;
 
@@ -9,6 +10,61 @@ pub(crate) mod strings {
     pub(crate) const RANGE: &str = "..";
     pub(crate) const DIVIDE: &str = "/";
     pub(crate) const PICK: &str = "$";
+    pub(crate) const INPUT: &str = "?";
+    pub(crate) const PLUS: &str = "+";
+    pub(crate) const MINUS: &str = "-";
+    pub(crate) const TIMES: &str = "*";
+    pub(crate) const INT_DIVIDE: &str = "//";
+    pub(crate) const MODULO: &str = "%";
+    pub(crate) const LIST_START: &str = "[";
+    pub(crate) const LIST_END: &str = "]";
+    pub(crate) const COMMA: &str = ",";
+    pub(crate) const PAREN_START: &str = "(";
+    pub(crate) const PAREN_END: &str = ")";
+    pub(crate) const FIELD: &str = ".";
+    pub(crate) const QUOTE: char = '"';
+    pub(crate) const ESCAPE: char = '\\';
+}
+
+/// Writes a string as a string literal, with quotes and escapes.
+pub(crate) fn quote(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push(strings::QUOTE);
+    for ch in string.chars() {
+        match ch {
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            strings::QUOTE | strings::ESCAPE => {
+                quoted.push(strings::ESCAPE);
+                quoted.push(ch);
+            }
+            _ => quoted.push(ch)
+        }
+    }
+    quoted.push(strings::QUOTE);
+    quoted
+}
+
+/// Reads a string literal at the start of the given string, which starts with a quote, and
+/// returns the unescaped content and the remainder after the closing quote.
+fn unquote(string: &str) -> Result<(String, &str), Error> {
+    let mut content = String::new();
+    let mut chars = string.char_indices().skip(1);
+    while let Some((pos, ch)) = chars.next() {
+        if ch == strings::QUOTE {
+            return Ok((content, &string[pos + ch.len_utf8()..]));
+        } else if ch == strings::ESCAPE {
+            match chars.next() {
+                Some((_, 'n')) => content.push('\n'),
+                Some((_, 't')) => content.push('\t'),
+                Some((_, ch)) => content.push(ch),
+                None => break
+            }
+        } else {
+            content.push(ch);
+        }
+    }
+    Err(PeatError(format!("Unterminated string literal {}", string)))
 }
 
 #[derive(PartialEq, Eq)]
@@ -18,8 +74,21 @@ pub(crate) enum Token {
     Range,
     Divide,
     Pick,
+    Input,
+    Plus,
+    Minus,
+    Times,
+    IntDivide,
+    Modulo,
+    ListStart,
+    ListEnd,
+    Comma,
+    ParenStart,
+    ParenEnd,
+    Field,
     Id(String),
     UInt(u64),
+    Str(String),
 }
 
 impl Clone for Token {
@@ -30,8 +99,21 @@ impl Clone for Token {
             Token::Range => { Token::Range }
             Token::Divide => { Token::Divide }
             Token::Pick => { Token::Pick }
+            Token::Input => { Token::Input }
+            Token::Plus => { Token::Plus }
+            Token::Minus => { Token::Minus }
+            Token::Times => { Token::Times }
+            Token::IntDivide => { Token::IntDivide }
+            Token::Modulo => { Token::Modulo }
+            Token::ListStart => { Token::ListStart }
+            Token::ListEnd => { Token::ListEnd }
+            Token::Comma => { Token::Comma }
+            Token::ParenStart => { Token::ParenStart }
+            Token::ParenEnd => { Token::ParenEnd }
+            Token::Field => { Token::Field }
             Token::Id(id) => { Token::Id(id.clone()) }
             Token::UInt(ui) => { Token::UInt(*ui) }
+            Token::Str(string) => { Token::Str(string.clone()) }
         }
     }
 }
@@ -44,31 +126,58 @@ impl Display for Token {
             Token::Range => { f.write_str(strings::RANGE) }
             Token::Divide => { f.write_str(strings::DIVIDE) }
             Token::Pick => { f.write_str(strings::PICK) }
+            Token::Input => { f.write_str(strings::INPUT) }
+            Token::Plus => { f.write_str(strings::PLUS) }
+            Token::Minus => { f.write_str(strings::MINUS) }
+            Token::Times => { f.write_str(strings::TIMES) }
+            Token::IntDivide => { f.write_str(strings::INT_DIVIDE) }
+            Token::Modulo => { f.write_str(strings::MODULO) }
+            Token::ListStart => { f.write_str(strings::LIST_START) }
+            Token::ListEnd => { f.write_str(strings::LIST_END) }
+            Token::Comma => { f.write_str(strings::COMMA) }
+            Token::ParenStart => { f.write_str(strings::PAREN_START) }
+            Token::ParenEnd => { f.write_str(strings::PAREN_END) }
+            Token::Field => { f.write_str(strings::FIELD) }
             Token::Id(id) => { Display::fmt(id, f) }
             Token::UInt(ui) => { Display::fmt(ui, f) }
+            Token::Str(string) => { f.write_str(&quote(string)) }
         }
     }
 }
 
+/// A token and the columns it was read from.
+#[derive(Clone)]
+pub(crate) struct TokenAt {
+    pub(crate) token: Token,
+    pub(crate) span: Span,
+}
+
 pub(crate) struct Tokenizer {
     string: String,
+    n_chars: usize,
 }
 
-fn is_valid_id_start(ch: char) -> bool {
+pub(crate) fn is_valid_id_start(ch: char) -> bool {
     ch.is_alphabetic() || ch == '_'
 }
 
-fn is_valid_id_part(ch: char) -> bool {
+pub(crate) fn is_valid_id_part(ch: char) -> bool {
     ch.is_alphanumeric() || ch == '_'
 }
 
 impl Tokenizer {
     pub(crate) fn new(string: String) -> Tokenizer {
-        Tokenizer { string }
+        let n_chars = string.chars().count();
+        Tokenizer { string, n_chars }
+    }
+
+    /// The column at which the remaining string starts, not counting leading whitespace.
+    fn col(&self) -> usize {
+        self.n_chars - self.string.trim_start().chars().count() + 1
     }
 
     fn next_token_and_remainder(&mut self) -> Result<Option<(Token, String)>, Error> {
-        let trimmed = self.string.trim();
+        let trimmed = self.string.trim_start();
         if trimmed.is_empty() {
             Ok(None)
         } else if let Some(stripped) = trimmed.strip_prefix(strings::ASSIGN) {
@@ -77,41 +186,75 @@ impl Tokenizer {
             Ok(Some((Token::Iterate, String::from(stripped))))
         } else if let Some(stripped) = trimmed.strip_prefix(strings::RANGE) {
             Ok(Some((Token::Range, String::from(stripped))))
+        } else if let Some(stripped) = trimmed.strip_prefix(strings::INT_DIVIDE) {
+            Ok(Some((Token::IntDivide, String::from(stripped))))
         } else if let Some(stripped) = trimmed.strip_prefix(strings::DIVIDE) {
             Ok(Some((Token::Divide, String::from(stripped))))
         } else if let Some(stripped) = trimmed.strip_prefix(strings::PICK) {
             Ok(Some((Token::Pick, String::from(stripped))))
+        } else if let Some(stripped) = trimmed.strip_prefix(strings::INPUT) {
+            Ok(Some((Token::Input, String::from(stripped))))
+        } else if let Some(stripped) = trimmed.strip_prefix(strings::PLUS) {
+            Ok(Some((Token::Plus, String::from(stripped))))
+        } else if let Some(stripped) = trimmed.strip_prefix(strings::MINUS) {
+            Ok(Some((Token::Minus, String::from(stripped))))
+        } else if let Some(stripped) = trimmed.strip_prefix(strings::TIMES) {
+            Ok(Some((Token::Times, String::from(stripped))))
+        } else if let Some(stripped) = trimmed.strip_prefix(strings::MODULO) {
+            Ok(Some((Token::Modulo, String::from(stripped))))
+        } else if let Some(stripped) = trimmed.strip_prefix(strings::LIST_START) {
+            Ok(Some((Token::ListStart, String::from(stripped))))
+        } else if let Some(stripped) = trimmed.strip_prefix(strings::LIST_END) {
+            Ok(Some((Token::ListEnd, String::from(stripped))))
+        } else if let Some(stripped) = trimmed.strip_prefix(strings::COMMA) {
+            Ok(Some((Token::Comma, String::from(stripped))))
+        } else if let Some(stripped) = trimmed.strip_prefix(strings::PAREN_START) {
+            Ok(Some((Token::ParenStart, String::from(stripped))))
+        } else if let Some(stripped) = trimmed.strip_prefix(strings::PAREN_END) {
+            Ok(Some((Token::ParenEnd, String::from(stripped))))
+        } else if let Some(stripped) = trimmed.strip_prefix(strings::FIELD) {
+            Ok(Some((Token::Field, String::from(stripped))))
+        } else if trimmed.starts_with(strings::QUOTE) {
+            let (string, remainder) = unquote(trimmed)?;
+            Ok(Some((Token::Str(string), String::from(remainder))))
         } else if trimmed.starts_with(is_valid_id_start) {
             let pos =
-                trimmed.find(|ch| { !is_valid_id_part(ch) }).unwrap_or_else(|| trimmed.len());
+                trimmed.find(|ch| { !is_valid_id_part(ch) }).unwrap_or(trimmed.len());
             let (id_str, str_new) = trimmed.split_at(pos);
             let id_string = String::from(id_str);
             let remainder = String::from(str_new);
             Ok(Some((Token::Id(id_string), remainder)))
-        } else if trimmed.starts_with(|ch: char| ch.is_digit(10)) {
+        } else if trimmed.starts_with(|ch: char| ch.is_ascii_digit()) {
             let pos =
                 trimmed
-                    .find(|ch: char| { !ch.is_digit(10) }).unwrap_or_else(|| trimmed.len());
+                    .find(|ch: char| { !ch.is_ascii_digit() }).unwrap_or(trimmed.len());
             let (num_str, str_new) = trimmed.split_at(pos);
-            let number = num_str.parse::<u64>().unwrap();
+            let number =
+                num_str.parse::<u64>()
+                    .map_err(|_| PeatError(format!("Number {} is too large.", num_str)))?;
             let remainder = String::from(str_new);
             Ok(Some((Token::UInt(number), remainder)))
         } else {
-            Err(PeatError(format!("Unexpected token {}", trimmed)))
+            Err(PeatError(format!("Unexpected token {}", trimmed.trim_end())))
         }
     }
 
-    pub(crate) fn strip_token(&mut self) -> Result<Option<Token>, Error> {
-        match self.next_token_and_remainder()? {
+    pub(crate) fn strip_token(&mut self) -> Result<Option<TokenAt>, Error> {
+        let col = self.col();
+        let token_and_remainder =
+            self.next_token_and_remainder().map_err(|error| error.at(Span::new(col, 1)))?;
+        match token_and_remainder {
             None => Ok(None),
             Some((token, remainder)) => {
+                let len =
+                    self.string.trim_start().chars().count() - remainder.chars().count();
                 self.string = remainder;
-                Ok(Some(token))
+                Ok(Some(TokenAt { token, span: Span::new(col, len) }))
             }
         }
     }
 
-    pub(crate) fn write_to_vec(mut self) -> Result<Vec<Token>, Error> {
+    pub(crate) fn write_to_vec(mut self) -> Result<Vec<TokenAt>, Error> {
         let mut tokens = Vec::new();
         loop {
             match self.strip_token()? {
@@ -123,3 +266,26 @@ impl Tokenizer {
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::Tokenizer;
+
+    fn spans(string: &str) -> Vec<(usize, usize)> {
+        let tokens = Tokenizer::new(String::from(string)).write_to_vec().unwrap();
+        tokens.iter().map(|token_at| (token_at.span.col, token_at.span.len)).collect()
+    }
+
+    #[test]
+    fn spans_count_characters_from_one() {
+        assert_eq!(spans("X = 1 + + 2   "), vec![(1, 1), (3, 1), (5, 1), (7, 1), (9, 1), (11, 1)]);
+        assert_eq!(spans("É <- 0 .. 10"), vec![(1, 1), (3, 2), (6, 1), (8, 2), (11, 2)]);
+        assert_eq!(spans("  \"a b\", x"), vec![(3, 5), (8, 1), (10, 1)]);
+    }
+
+    #[test]
+    fn errors_point_at_the_unexpected_character() {
+        let error = Tokenizer::new(String::from("1 + @")).write_to_vec().err().unwrap();
+        assert!(error.to_string().starts_with("At column 5:"), "{}", error);
+    }
+}
@@ -9,6 +9,26 @@ pub(crate) fn get_empty_bindings() -> Bindings {
     matryoshka::MatryoshkaMap::new()
 }
 
-pub(crate) fn new_bindings_iter<'a>() -> BindingsIterator<'a> {
-    Box::new(std::iter::once(Ok(get_empty_bindings())))
+pub(crate) fn format_bindings(entries: &[(String, Value)]) -> String {
+    if entries.is_empty() {
+        String::from("[empty]")
+    } else {
+        let entry_strings: Vec<String> =
+            entries.iter().map(|(id, value)| {
+                format!("{} = {}", id, value.to_literal())
+            }).collect();
+        entry_strings.join(", ")
+    }
+}
+
+pub(crate) fn bindings_from_entries(entries: &[(String, Value)]) -> Bindings {
+    let mut bindings = get_empty_bindings();
+    for (id, value) in entries {
+        bindings = bindings.with_value(id.clone(), value.clone());
+    }
+    bindings
+}
+
+pub(crate) fn new_bindings_iter<'a>(bindings: Bindings) -> BindingsIterator<'a> {
+    Box::new(std::iter::once(Ok(bindings)))
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
 use std::fmt::{Display, Formatter};
 use std::fmt;
 use crate::util::error::Error;
+use crate::code::tokenize;
+use crate::code::arithmetic::Operator;
 use std::ops::Range;
 
 #[derive(Copy, Clone)]
@@ -15,25 +17,39 @@ pub(crate) struct UIntRangeRange {
     divisor: UIntRange,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
+pub(crate) struct ListGroups {
+    list: Vec<Value>,
+    divisor: UIntRange,
+}
+
+#[derive(Clone)]
 pub(crate) enum Value {
     UInt(u64),
     UIntRange(UIntRange),
     UIntRangeRange(UIntRangeRange),
+    Str(String),
+    List(Vec<Value>),
+    ListGroups(ListGroups),
+    Record(Vec<(String, Value)>),
 }
 
 impl UIntRange {
     pub(crate) fn new(from: u64, until: u64) -> UIntRange { UIntRange { from, until } }
-    pub(crate) fn len(&self) -> u64 { self.until - self.from }
+    pub(crate) fn len(&self) -> Result<u64, Error> {
+        self.until.checked_sub(self.from).ok_or_else(|| {
+            Error::from(format!("Range {} ends before it starts.", self))
+        })
+    }
     pub(crate) fn contains(&self, i: u64) -> bool { i >= self.from && i < self.until }
-    pub(crate) fn to_range(&self) -> Range<u64> { self.from..self.until }
+    pub(crate) fn to_range(self) -> Range<u64> { self.from..self.until }
 }
 
 fn ceil_div(dividend: u64, divisor: u64) -> Result<u64, Error> {
     if divisor == 0 {
         return Err(Error::from("Division by zero"));
     }
-    Ok((dividend + divisor - 1) / divisor)
+    Ok(dividend.div_ceil(divisor))
 }
 
 impl UIntRangeRange {
@@ -45,15 +61,27 @@ impl UIntRangeRange {
             return Err(Error::from(format!("Pick {} is not in range {}.", g, self.divisor)));
         }
         let l = self.dividend.from;
-        let n = self.dividend.len();
-        let m = self.divisor.len();
-        let lg = self.divisor.from;
-        let from = l + ceil_div((g - lg) * n, m)?;
-        let until = l + ceil_div((g - lg + 1) * n, m)?;
+        let n = self.dividend.len()?;
+        let m = self.divisor.len()?;
+        let i = g - self.divisor.from;
+        let from = Operator::Add.apply(l, ceil_div(Operator::Multiply.apply(i, n)?, m)?)?;
+        let until = Operator::Add.apply(l, ceil_div(Operator::Multiply.apply(i + 1, n)?, m)?)?;
         Ok(UIntRange::new(from, until))
     }
 }
 
+impl ListGroups {
+    pub(crate) fn new(list: Vec<Value>, divisor: UIntRange) -> ListGroups {
+        ListGroups { list, divisor }
+    }
+    /// Picks a group of list elements, grouped the same way as the indices of the list.
+    pub(crate) fn pick(&self, g: u64) -> Result<Vec<Value>, Error> {
+        let indices = UIntRange::new(0, self.list.len() as u64);
+        let picked = UIntRangeRange::new(indices, self.divisor).pick(g)?;
+        Ok(self.list[picked.from as usize..picked.until as usize].to_vec())
+    }
+}
+
 impl Value {
     pub(crate) fn as_int(&self) -> Result<u64, Error> {
         match self {
@@ -65,7 +93,13 @@ impl Value {
             Value::UIntRangeRange(ui_rng_rng) =>
                 Err(Error::from(
                     format!("Expected integer, but got range of ranges {}.", ui_rng_rng)
-                ))
+                )),
+            Value::Str(string) =>
+                Err(Error::from(
+                    format!("Expected integer, but got string {}.", tokenize::quote(string))
+                )),
+            Value::List(_) | Value::ListGroups(_) | Value::Record(_) =>
+                Err(Error::from(format!("Expected integer, but got {}.", self.describe())))
         }
     }
 
@@ -79,26 +113,138 @@ impl Value {
             Value::UIntRangeRange(ui_rng_rng) =>
                 Err(Error::from(
                     format!("Expected range, but got range of ranges {}.", ui_rng_rng)
-                ))
+                )),
+            Value::Str(string) =>
+                Err(Error::from(
+                    format!("Expected range, but got string {}.", tokenize::quote(string))
+                )),
+            Value::List(_) | Value::ListGroups(_) | Value::Record(_) =>
+                Err(Error::from(format!("Expected range, but got {}.", self.describe())))
         }
     }
 
-    pub(crate) fn as_range_range(&self) -> Result<UIntRangeRange, Error> {
+    /// The string to use when concatenating this value with a string.
+    pub(crate) fn as_concat_str(&self) -> Result<String, Error> {
         match self {
-            Value::UInt(ui) =>
-                Err(Error::from(
-                    format!("Expected range of ranges, but got integer {}", ui)
-                )),
+            Value::UInt(ui) => Ok(ui.to_string()),
+            Value::Str(string) => Ok(string.clone()),
             Value::UIntRange(ui_rng) =>
-                Err(Error::from(
-                    format!("Expected range of ranges, but got range {}", ui_rng)
-                )),
-            Value::UIntRangeRange(ui_rng_rng) => Ok(*ui_rng_rng)
+                Err(Error::from(format!("Cannot concatenate range {}.", ui_rng))),
+            Value::UIntRangeRange(ui_rng_rng) =>
+                Err(Error::from(format!("Cannot concatenate range of ranges {}.", ui_rng_rng))),
+            Value::List(_) | Value::ListGroups(_) | Value::Record(_) =>
+                Err(Error::from(format!("Cannot concatenate {}.", self.describe())))
+        }
+    }
+
+    pub(crate) fn as_string(&self) -> Result<String, Error> {
+        match self {
+            Value::Str(string) => Ok(string.clone()),
+            _ => Err(Error::from(format!("Expected string, but got {}.", self.describe())))
+        }
+    }
+
+    pub(crate) fn get_field(&self, field: &str) -> Result<Value, Error> {
+        match self {
+            Value::Record(fields) => {
+                fields.iter().find(|(name, _)| name == field).map(|(_, value)| value.clone())
+                    .ok_or_else(|| Error::from(format!("No field {} in {}.", field,
+                                                       self.describe())))
+            }
+            _ => Err(Error::from(format!("Expected record with field {}, but got {}.", field,
+                                         self.describe())))
+        }
+    }
+
+    /// Adds the value to the items of a list literal, where a range stands for its integers, so
+    /// that `[1 .. 23, "X", "Y"]` has 24 items.
+    pub(crate) fn push_to_list(self, list: &mut Vec<Value>) -> Result<(), Error> {
+        match self {
+            Value::UIntRange(ui_rng) => list.extend(ui_rng.to_range().map(Value::new_int)),
+            Value::UInt(_) | Value::Str(_) | Value::Record(_) => list.push(self),
+            Value::UIntRangeRange(_) | Value::List(_) | Value::ListGroups(_) => {
+                return Err(Error::from(format!(
+                    "Expected integer, string, range or record as item of list, but got {}.",
+                    self.describe()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The values to iterate over for an iteration declaration.
+    pub(crate) fn into_iter_values(self) -> Result<Box<dyn Iterator<Item=Value>>, Error> {
+        match self {
+            Value::UIntRange(ui_rng) => Ok(Box::new(ui_rng.to_range().map(Value::new_int))),
+            Value::List(list) => Ok(Box::new(list.into_iter())),
+            _ => Err(Error::from(format!("Expected range or list, but got {}.", self.describe())))
+        }
+    }
+
+    /// The number of values into_iter_values yields, without producing them.
+    pub(crate) fn n_iter_values(&self) -> Result<u64, Error> {
+        match self {
+            Value::UIntRange(ui_rng) => Ok(ui_rng.until.saturating_sub(ui_rng.from)),
+            Value::List(list) => Ok(list.len() as u64),
+            _ => Err(Error::from(format!("Expected range or list, but got {}.", self.describe())))
+        }
+    }
+
+    /// Divides a range or list into groups, one group for each member of the divisor.
+    pub(crate) fn divide(self, divisor: UIntRange) -> Result<Value, Error> {
+        match self {
+            Value::UIntRange(ui_rng) =>
+                Ok(Value::UIntRangeRange(UIntRangeRange::new(ui_rng, divisor))),
+            Value::List(list) => Ok(Value::ListGroups(ListGroups::new(list, divisor))),
+            _ => Err(Error::from(format!("Expected range or list to divide, but got {}.",
+                                         self.describe())))
+        }
+    }
+
+    pub(crate) fn pick(&self, g: u64) -> Result<Value, Error> {
+        match self {
+            Value::UIntRangeRange(ui_rng_rng) => Ok(Value::UIntRange(ui_rng_rng.pick(g)?)),
+            Value::ListGroups(list_groups) => Ok(Value::List(list_groups.pick(g)?)),
+            _ => Err(Error::from(format!("Expected groups to pick from, but got {}.",
+                                         self.describe())))
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Value::UInt(ui) => format!("integer {}", ui),
+            Value::UIntRange(ui_rng) => format!("range {}", ui_rng),
+            Value::UIntRangeRange(ui_rng_rng) => format!("range of ranges {}", ui_rng_rng),
+            Value::Str(string) => format!("string {}", tokenize::quote(string)),
+            Value::List(_) => format!("list {}", self.to_literal()),
+            Value::ListGroups(list_groups) => format!("groups of list {}", list_groups),
+            Value::Record(_) => format!("record {}", self.to_literal())
+        }
+    }
+
+    /// Writes the value the way it would be written in a peat file, e.g. with strings quoted.
+    pub(crate) fn to_literal(&self) -> String {
+        match self {
+            Value::Str(string) => tokenize::quote(string),
+            Value::List(list) => {
+                let item_strings: Vec<String> = list.iter().map(Value::to_literal).collect();
+                format!("[{}]", item_strings.join(", "))
+            }
+            Value::Record(fields) => {
+                let field_strings: Vec<String> =
+                    fields.iter().map(|(name, value)| {
+                        format!("{}: {}", name, value.to_literal())
+                    }).collect();
+                format!("{{{}}}", field_strings.join(", "))
+            }
+            _ => self.to_string()
         }
     }
 
     pub(crate) fn new_int(ui: u64) -> Value { Value::UInt(ui) }
 
+    pub(crate) fn new_str(string: String) -> Value { Value::Str(string) }
+
     pub(crate) fn new_range(from: u64, until: u64) -> Value {
         Value::UIntRange(UIntRange { from, until })
     }
@@ -116,6 +262,13 @@ impl Display for UIntRangeRange {
     }
 }
 
+impl Display for ListGroups {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let list = Value::List(self.list.clone()).to_literal();
+        Display::fmt(format!("{} / {}", list, self.divisor).as_str(), f)
+    }
+}
+
 impl Display for Value {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
@@ -124,6 +277,17 @@ impl Display for Value {
             Value::UIntRangeRange(uint_range_range) => {
                 Display::fmt(uint_range_range, f)
             }
+            Value::Str(string) => { f.write_str(string) }
+            Value::List(list) => {
+                let item_strings: Vec<String> = list.iter().map(Value::to_string).collect();
+                f.write_str(&item_strings.join(" "))
+            }
+            Value::ListGroups(list_groups) => { Display::fmt(list_groups, f) }
+            Value::Record(fields) => {
+                let value_strings: Vec<String> =
+                    fields.iter().map(|(_, value)| value.to_string()).collect();
+                f.write_str(&value_strings.join("\t"))
+            }
         }
     }
 }
@@ -1,10 +1,33 @@
+use crate::emit;
+use crate::util::duration;
 use crate::util::error::Error;
+use crate::util::log;
 use std::env;
+use std::time::Duration;
 use clap::{App, Arg};
 
 pub(crate) struct Config {
     pub(crate) parse_only: bool,
     pub(crate) dry_run: bool,
+    pub(crate) count: bool,
+    pub(crate) count_by: Option<String>,
+    pub(crate) emit_bindings: Option<emit::Format>,
+    pub(crate) log_level: log::Level,
+    pub(crate) jobs: Option<u64>,
+    pub(crate) max_failures: Option<u64>,
+    pub(crate) shell: Option<String>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) retries: u32,
+    pub(crate) retry_delay: Duration,
+    pub(crate) retry_exit_codes: Option<Vec<i32>>,
+    pub(crate) state_dir: Option<String>,
+    pub(crate) log_dir: Option<String>,
+    pub(crate) log_name: Option<String>,
+    pub(crate) export_env: bool,
+    pub(crate) report: Option<String>,
+    pub(crate) progress_interval: Duration,
+    pub(crate) status_file: Option<String>,
+    pub(crate) definitions: Vec<String>,
     pub(crate) input_file: Option<String>,
 }
 
@@ -12,8 +35,74 @@ mod names {
     pub(crate) const FILE: &str = "FILE";
     pub(crate) const PARSE_ONLY: &str = "PARSE_ONLY";
     pub(crate) const DRY_RUN: &str = "DRY_RUN";
+    pub(crate) const COUNT: &str = "COUNT";
+    pub(crate) const COUNT_BY: &str = "COUNT_BY";
+    pub(crate) const EMIT_BINDINGS: &str = "EMIT_BINDINGS";
+    pub(crate) const QUIET: &str = "QUIET";
+    pub(crate) const VERBOSE: &str = "VERBOSE";
+    pub(crate) const JOBS: &str = "JOBS";
+    pub(crate) const FAIL_FAST: &str = "FAIL_FAST";
+    pub(crate) const KEEP_GOING: &str = "KEEP_GOING";
+    pub(crate) const MAX_FAILURES: &str = "MAX_FAILURES";
+    pub(crate) const SHELL: &str = "SHELL";
+    pub(crate) const TIMEOUT: &str = "TIMEOUT";
+    pub(crate) const RETRIES: &str = "RETRIES";
+    pub(crate) const RETRY_DELAY: &str = "RETRY_DELAY";
+    pub(crate) const RETRY_ON: &str = "RETRY_ON";
+    pub(crate) const STATE_DIR: &str = "STATE_DIR";
+    pub(crate) const LOG_DIR: &str = "LOG_DIR";
+    pub(crate) const LOG_NAME: &str = "LOG_NAME";
+    pub(crate) const EXPORT_ENV: &str = "EXPORT_ENV";
+    pub(crate) const REPORT: &str = "REPORT";
+    pub(crate) const PROGRESS_INTERVAL: &str = "PROGRESS_INTERVAL";
+    pub(crate) const STATUS_FILE: &str = "STATUS_FILE";
+    pub(crate) const DEFINE: &str = "DEFINE";
 }
 
+fn parse_positive_int(string: &str, what: &str) -> Result<u64, Error> {
+    match string.parse::<u64>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(Error::from(format!("{} needs to be a positive integer, but got {}.",
+                                     what, string)))
+    }
+}
+
+fn parse_jobs(jobs_str: &str) -> Result<u64, Error> {
+    parse_positive_int(jobs_str, "Number of jobs")
+}
+
+fn parse_max_failures(max_failures_str: &str) -> Result<u64, Error> {
+    parse_positive_int(max_failures_str, "Maximum number of failures")
+}
+
+/// Progress is printed on a timer, so an interval of zero would print all the time.
+fn parse_progress_interval(interval_str: &str) -> Result<Duration, Error> {
+    let interval = duration::parse_duration(interval_str)?;
+    if interval.is_zero() {
+        return Err(Error::from("Progress interval needs to be at least 1s."));
+    }
+    Ok(interval)
+}
+
+fn parse_retries(retries_str: &str) -> Result<u32, Error> {
+    retries_str.parse::<u32>().map_err(|_| {
+        Error::from(format!("Number of retries needs to be a non-negative integer, but got {}.",
+                            retries_str))
+    })
+}
+
+fn parse_exit_codes(exit_codes_str: &str) -> Result<Vec<i32>, Error> {
+    exit_codes_str.split(',').map(|code_str| {
+        code_str.trim().parse::<i32>().map_err(|_| {
+            Error::from(format!("Expected comma-separated exit codes, but got {}.",
+                                exit_codes_str))
+        })
+    }).collect()
+}
+
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(10);
+const DEFAULT_PROGRESS_INTERVAL: Duration = Duration::from_secs(60);
+
 pub(crate) fn get_config() -> Result<Config, Error> {
     let app =
         App::new(clap::crate_name!())
@@ -30,13 +119,189 @@ pub(crate) fn get_config() -> Result<Config, Error> {
                 .long("dry-run")
                 .takes_value(false)
                 .help("Parse and evaluate expressions, but do not actually run jobs."))
+            .arg(Arg::with_name(names::COUNT)
+                .long("count")
+                .takes_value(false)
+                .conflicts_with_all(&[names::PARSE_ONLY, names::DRY_RUN, names::COUNT_BY])
+                .help("Print the number of jobs, but do not run them."))
+            .arg(Arg::with_name(names::COUNT_BY)
+                .long("count-by")
+                .value_name("VAR")
+                .takes_value(true)
+                .conflicts_with_all(&[names::PARSE_ONLY, names::DRY_RUN])
+                .help("Print the number of jobs for each value of VAR, but do not run them."))
+            .arg(Arg::with_name(names::EMIT_BINDINGS)
+                .long("emit-bindings")
+                .value_name("FORMAT")
+                .takes_value(true)
+                .possible_values(&emit::FORMAT_NAMES)
+                .conflicts_with_all(&[names::PARSE_ONLY, names::DRY_RUN, names::COUNT,
+                    names::COUNT_BY])
+                .help("Write the bindings of all jobs to stdout as tsv, json or jsonl, but do not \
+                run them."))
+            .arg(Arg::with_name(names::QUIET)
+                .short("q")
+                .long("quiet")
+                .takes_value(false)
+                .conflicts_with(names::VERBOSE)
+                .help("Only print warnings, such as about failed jobs."))
+            .arg(Arg::with_name(names::VERBOSE)
+                .short("v")
+                .long("verbose")
+                .takes_value(false)
+                .multiple(true)
+                .help("Also print which script each job runs, and with --log-dir, which jobs \
+                run and succeed. Use twice to also print the scripts."))
+            .arg(Arg::with_name(names::JOBS)
+                .short("j")
+                .long("jobs")
+                .value_name("N")
+                .takes_value(true)
+                .help("Run up to N jobs at the same time. Overrides @JOBS in the peat file."))
+            .arg(Arg::with_name(names::FAIL_FAST)
+                .long("fail-fast")
+                .takes_value(false)
+                .conflicts_with_all(&[names::KEEP_GOING, names::MAX_FAILURES])
+                .help("Do not start any more jobs after the first job failed."))
+            .arg(Arg::with_name(names::KEEP_GOING)
+                .long("keep-going")
+                .takes_value(false)
+                .conflicts_with(names::MAX_FAILURES)
+                .help("Run all jobs, even if some failed. This is the default."))
+            .arg(Arg::with_name(names::MAX_FAILURES)
+                .long("max-failures")
+                .value_name("K")
+                .takes_value(true)
+                .help("Do not start any more jobs after K jobs failed."))
+            .arg(Arg::with_name(names::SHELL)
+                .long("shell")
+                .value_name("COMMAND")
+                .takes_value(true)
+                .help("Run scripts with COMMAND, such as bash or python3, instead of sh. \
+                Overrides @INTERPRETER in the peat file."))
+            .arg(Arg::with_name(names::TIMEOUT)
+                .long("timeout")
+                .value_name("DURATION")
+                .takes_value(true)
+                .help("Stop jobs that run longer than DURATION, such as 90s, 30m or 2h. Overrides \
+                @TIMEOUT in the peat file."))
+            .arg(Arg::with_name(names::RETRIES)
+                .long("retries")
+                .value_name("N")
+                .takes_value(true)
+                .help("Run a failed job up to N more times."))
+            .arg(Arg::with_name(names::RETRY_DELAY)
+                .long("retry-delay")
+                .value_name("DURATION")
+                .takes_value(true)
+                .requires(names::RETRIES)
+                .help("Wait DURATION before the first retry, and twice as long before each \
+                further retry. Default is 10s."))
+            .arg(Arg::with_name(names::RETRY_ON)
+                .long("retry-on")
+                .value_name("CODES")
+                .takes_value(true)
+                .requires(names::RETRIES)
+                .help("Only retry jobs that failed with one of the comma-separated exit CODES."))
+            .arg(Arg::with_name(names::STATE_DIR)
+                .long("state-dir")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Keep a journal of finished jobs in DIR and skip jobs that already \
+                succeeded."))
+            .arg(Arg::with_name(names::LOG_DIR)
+                .long("log-dir")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Write the output of each job to its own .out and .err file in DIR."))
+            .arg(Arg::with_name(names::LOG_NAME)
+                .long("log-name")
+                .value_name("TEMPLATE")
+                .takes_value(true)
+                .requires(names::LOG_DIR)
+                .help("Name log files after TEMPLATE, which may contain placeholders like \
+                job_<:I:>. Default is job_ followed by the job number."))
+            .arg(Arg::with_name(names::EXPORT_ENV)
+                .long("export-env")
+                .takes_value(false)
+                .help("Also give each job its bindings as environment variables like PEAT_I, \
+                and PEAT_JOB_INDEX and PEAT_JOB_COUNT."))
+            .arg(Arg::with_name(names::REPORT)
+                .long("report")
+                .value_name("FILE")
+                .takes_value(true)
+                .help("Write a JSON Lines record for each job to FILE, followed by a summary, \
+                and keep the scripts in FILE.scripts."))
+            .arg(Arg::with_name(names::PROGRESS_INTERVAL)
+                .long("progress-interval")
+                .value_name("DURATION")
+                .takes_value(true)
+                .help("Print how many jobs are done once per DURATION. Default is 1m."))
+            .arg(Arg::with_name(names::STATUS_FILE)
+                .long("status-file")
+                .value_name("FILE")
+                .takes_value(true)
+                .help("Keep FILE up to date with how many jobs are done, after each job."))
+            .arg(Arg::with_name(names::DEFINE)
+                .short("D")
+                .long("define")
+                .value_name("NAME=VALUE")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Set variable NAME to VALUE, replacing its declaration in the peat file."))
             .arg(Arg::with_name(names::FILE)
                 .value_name("peat file")
                 .takes_value(true));
     let matches = app.get_matches_safe()?;
     let parse_only = matches.is_present(names::PARSE_ONLY);
     let dry_run = matches.is_present(names::DRY_RUN);
+    let count = matches.is_present(names::COUNT);
+    let count_by = matches.value_of(names::COUNT_BY).map(String::from);
+    let emit_bindings =
+        matches.value_of(names::EMIT_BINDINGS).map(emit::Format::parse).transpose()?;
+    let log_level =
+        if matches.is_present(names::QUIET) {
+            log::Level::Quiet
+        } else {
+            match matches.occurrences_of(names::VERBOSE) {
+                0 => log::Level::Normal,
+                1 => log::Level::Verbose,
+                _ => log::Level::Debug
+            }
+        };
+    let jobs = matches.value_of(names::JOBS).map(parse_jobs).transpose()?;
+    let max_failures =
+        if matches.is_present(names::FAIL_FAST) {
+            Some(1)
+        } else {
+            matches.value_of(names::MAX_FAILURES).map(parse_max_failures).transpose()?
+        };
+    let shell = matches.value_of(names::SHELL).map(String::from);
+    let timeout = matches.value_of(names::TIMEOUT).map(duration::parse_duration).transpose()?;
+    let retries = matches.value_of(names::RETRIES).map(parse_retries).transpose()?.unwrap_or(0);
+    let retry_delay =
+        matches.value_of(names::RETRY_DELAY).map(duration::parse_duration).transpose()?
+            .unwrap_or(DEFAULT_RETRY_DELAY);
+    let retry_exit_codes =
+        matches.value_of(names::RETRY_ON).map(parse_exit_codes).transpose()?;
+    let state_dir = matches.value_of(names::STATE_DIR).map(String::from);
+    let log_dir = matches.value_of(names::LOG_DIR).map(String::from);
+    let log_name = matches.value_of(names::LOG_NAME).map(String::from);
+    let export_env = matches.is_present(names::EXPORT_ENV);
+    let report = matches.value_of(names::REPORT).map(String::from);
+    let progress_interval =
+        matches.value_of(names::PROGRESS_INTERVAL).map(parse_progress_interval).transpose()?
+            .unwrap_or(DEFAULT_PROGRESS_INTERVAL);
+    let status_file = matches.value_of(names::STATUS_FILE).map(String::from);
+    let definitions =
+        matches.values_of(names::DEFINE).map(|values| {
+            values.map(String::from).collect()
+        }).unwrap_or_default();
     let input_file =
         matches.value_of(names::FILE).map(|s| { String::from(s) });
-    Ok(Config { parse_only, dry_run, input_file })
+    Ok(Config { parse_only, dry_run, count, count_by, emit_bindings, log_level, jobs,
+                max_failures, shell, timeout, retries, retry_delay, retry_exit_codes, state_dir,
+                log_dir, log_name, export_env, report, progress_interval, status_file,
+                definitions, input_file })
 }
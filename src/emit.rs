@@ -0,0 +1,78 @@
+use std::io::{self, BufWriter, Write};
+use crate::code::types::{Bindings, BindingsIterator};
+use crate::code::value::Value;
+use crate::json::bindings_json;
+use crate::util::error::Error;
+
+/// Formats in which to write the bindings of all jobs, instead of running them.
+#[derive(Copy, Clone)]
+pub(crate) enum Format {
+    /// With a header line of variable names, and one line per job.
+    Tsv,
+    /// One array of objects.
+    Json,
+    /// One object per line.
+    Jsonl,
+}
+
+pub(crate) const FORMAT_NAMES: [&str; 3] = ["tsv", "json", "jsonl"];
+
+impl Format {
+    pub(crate) fn parse(string: &str) -> Result<Format, Error> {
+        match string {
+            "tsv" => Ok(Format::Tsv),
+            "json" => Ok(Format::Json),
+            "jsonl" => Ok(Format::Jsonl),
+            _ => Err(Error::from(format!("Expected one of {}, but got {}.",
+                                         FORMAT_NAMES.join(", "), string)))
+        }
+    }
+}
+
+/// Escapes tabs, newlines and backslashes, so that each value stays in its own cell.
+fn tsv_cell(value: &Value) -> String {
+    let string = match value {
+        Value::Str(string) => string.clone(),
+        _ => value.to_literal()
+    };
+    string.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+fn row(ids: &[String], bindings: &Bindings) -> Vec<(String, Value)> {
+    ids.iter().filter_map(|id| {
+        bindings.get(id).map(|value| (id.clone(), value))
+    }).collect()
+}
+
+/// Writes the bindings of all jobs to stdout, with the variables in the given order.
+pub(crate) fn emit_bindings(format: Format, ids: &[String], bindings_iter: BindingsIterator)
+                            -> Result<(), Error> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    match format {
+        Format::Tsv => {
+            writeln!(out, "{}", ids.join("\t"))?;
+            for bindings_result in bindings_iter {
+                let cells: Vec<String> =
+                    row(ids, &bindings_result?).iter().map(|(_, value)| tsv_cell(value))
+                        .collect();
+                writeln!(out, "{}", cells.join("\t"))?;
+            }
+        }
+        Format::Json => {
+            write!(out, "[")?;
+            for (index, bindings_result) in bindings_iter.enumerate() {
+                let separator = if index == 0 { "\n" } else { ",\n" };
+                write!(out, "{}{}", separator, bindings_json(&row(ids, &bindings_result?)))?;
+            }
+            writeln!(out, "\n]")?;
+        }
+        Format::Jsonl => {
+            for bindings_result in bindings_iter {
+                writeln!(out, "{}", bindings_json(&row(ids, &bindings_result?)))?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::code::value::Value;
+
+const PREFIX: &str = "PEAT_";
+
+/// Replaces characters that are not allowed in the name of an environment variable, such as
+/// the dash in a column named read-count, or a letter outside ASCII, with underscores.
+fn env_name(name: &str) -> String {
+    let sanitized: String =
+        name.chars().map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' }).collect();
+    format!("{}{}", PREFIX, sanitized)
+}
+
+/// Environment variables that give a job its bindings, such as PEAT_I for variable I, and for a
+/// record also each field, such as PEAT_SAMPLE_reads. Values are written as in placeholders.
+pub(crate) fn job_env(index: u64, n_jobs: Option<u64>, bindings: &[(String, Value)])
+                      -> Vec<(String, String)> {
+    let mut env = Vec::<(String, String)>::new();
+    for (id, value) in bindings {
+        env.push((env_name(id), value.to_string()));
+        if let Value::Record(fields) = value {
+            for (field, field_value) in fields {
+                env.push((env_name(&format!("{}_{}", id, field)), field_value.to_string()));
+            }
+        }
+    }
+    env.push((format!("{}JOB_INDEX", PREFIX), index.to_string()));
+    if let Some(n_jobs) = n_jobs {
+        env.push((format!("{}JOB_COUNT", PREFIX), n_jobs.to_string()));
+    }
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::code::value::Value;
+    use super::job_env;
+
+    #[test]
+    fn names_only_have_ascii_letters_digits_and_underscores() {
+        let fields = vec![(String::from("read-count"), Value::new_int(5)),
+                          (String::from("1 ü.x"), Value::new_str(String::from("a b")))];
+        let bindings = vec![(String::from("Süd"), Value::Record(fields))];
+        let names: Vec<String> =
+            job_env(3, None, &bindings).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names,
+                   vec!["PEAT_S_d", "PEAT_S_d_read_count", "PEAT_S_d_1___x", "PEAT_JOB_INDEX"]);
+    }
+}
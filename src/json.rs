@@ -0,0 +1,74 @@
+use crate::code::value::Value;
+
+pub(crate) const NULL: &str = "null";
+
+pub(crate) fn json_string(string: &str) -> String {
+    let mut json = String::with_capacity(string.len() + 2);
+    json.push('"');
+    for ch in string.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => json.push(ch)
+        }
+    }
+    json.push('"');
+    json
+}
+
+pub(crate) fn json_object(fields: &[(&str, String)]) -> String {
+    let field_strings: Vec<String> =
+        fields.iter().map(|(name, json)| format!("{}:{}", json_string(name), json)).collect();
+    format!("{{{}}}", field_strings.join(","))
+}
+
+pub(crate) fn json_opt<T, F: Fn(T) -> String>(opt: Option<T>, to_json: F) -> String {
+    opt.map(to_json).unwrap_or_else(|| String::from(NULL))
+}
+
+/// Numbers, strings, lists and records map to their JSON counterparts, and ranges to strings.
+fn value_json(value: &Value) -> String {
+    match value {
+        Value::UInt(ui) => ui.to_string(),
+        Value::Str(string) => json_string(string),
+        Value::List(list) => {
+            let item_strings: Vec<String> = list.iter().map(value_json).collect();
+            format!("[{}]", item_strings.join(","))
+        }
+        Value::Record(fields) => bindings_json(fields),
+        _ => json_string(&value.to_string())
+    }
+}
+
+pub(crate) fn bindings_json(bindings: &[(String, Value)]) -> String {
+    let fields: Vec<(&str, String)> =
+        bindings.iter().map(|(id, value)| (id.as_str(), value_json(value))).collect();
+    json_object(&fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::code::value::Value;
+    use super::{bindings_json, json_string};
+
+    #[test]
+    fn strings_escape_quotes_backslashes_and_control_characters() {
+        assert_eq!(json_string(r#"say "hi" \ bye"#), r#""say \"hi\" \\ bye""#);
+        assert_eq!(json_string("a\nb\r\tc"), r#""a\nb\r\tc""#);
+        assert_eq!(json_string("\u{1}\u{1f}é"), "\"\\u0001\\u001fé\"");
+    }
+
+    #[test]
+    fn bindings_map_to_an_object_in_order() {
+        let bindings = vec![
+            (String::from("n"), Value::new_int(3)),
+            (String::from("s"), Value::new_str(String::from("x\"y"))),
+            (String::from("l"), Value::List(vec![Value::new_int(1), Value::new_int(2)])),
+        ];
+        assert_eq!(bindings_json(&bindings), r#"{"n":3,"s":"x\"y","l":[1,2]}"#);
+    }
+}
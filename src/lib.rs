@@ -1,29 +1,49 @@
-use code::types::Bindings;
 use util::error::Error;
+use util::log::{self, info, verbose};
 use code::{PeatCode, evaluate};
-use script_files::ScriptNameGenerator;
+use code::directive::Directives;
+use code::value::Value;
 use crate::config::Config;
+use crate::pool::JobSettings;
+use crate::retry::RetryPolicy;
+use crate::sh::Interpreter;
+use std::io::{self, Write};
+use std::process;
 
 pub mod util;
 mod code;
-mod substitute;
 mod sh;
 mod script_files;
 mod config;
+mod pool;
+mod summary;
+mod state;
+mod runner;
+mod logs;
+mod signals;
+mod retry;
+mod report;
+mod json;
+mod emit;
+mod job_env;
+mod progress;
 
 pub fn lib_main() {
     match config::get_config() {
         Ok(peat_config) => {
+            log::set_level(peat_config.log_level);
             match run(peat_config) {
                 Err(error) => {
-                    eprintln!("Error: {}", error)
+                    eprintln!("Error: {}", error);
+                    process::exit(1)
                 }
-                Ok(()) => println!("Done!")
+                Ok(()) => info!("Done!")
             }
         }
         Err(error) => {
             if error.is_real_error() {
-                eprintln!("Error: {}", error)
+                eprintln!("Error: {}", error);
+                process::exit(1)
             } else {
                 println!("{}", error)
             }
@@ -33,53 +53,117 @@ pub fn lib_main() {
 
 fn run(peat_config: Config) -> Result<(), Error> {
     let peat_code = code::get_peat_code(&peat_config.input_file)?;
-    println!("Peat file uses version {}", peat_code.version);
+    info!("Peat file uses version {}", peat_code.version);
     print_declarations(&peat_code);
+    if !peat_code.directives.is_empty() {
+        print_directives(&peat_code);
+    }
+    let overrides =
+        peat_config.definitions.iter().map(|definition| {
+            code::parse::parse_definition(definition)
+        }).collect::<Result<Vec<(String, Value)>, Error>>()?;
+    if !overrides.is_empty() {
+        info!("Overrides: {}", code::types::format_bindings(&overrides));
+    }
+    let ids = evaluate::variable_ids(&peat_code, &overrides);
+    let str_ids = evaluate::string_ids(&peat_code, &overrides);
+    peat_code.template.check_ids(&ids, &str_ids, &peat_code.body, &peat_code.file_name,
+                                 peat_code.body_line_number)?;
+    if let Some(log_name) = &peat_config.log_name {
+        logs::check_log_name(log_name, &ids, &str_ids)?;
+    }
+    if peat_config.count || peat_config.count_by.is_some() {
+        return print_counts(&peat_config, &peat_code, &overrides);
+    }
+    if let Some(format) = peat_config.emit_bindings {
+        let bindings_iter = evaluate::evaluate_declarations(&peat_code, &overrides);
+        return emit::emit_bindings(format, &ids, bindings_iter);
+    }
     if !peat_config.parse_only {
-        let bindings_iter = evaluate::evaluate_declarations(&peat_code);
-        println!("Now evaluating");
-        let mut script_name_gen = ScriptNameGenerator::from_temp_dir()?;
-        for bindings_result in bindings_iter {
-            let bindings = bindings_result?;
-            print_bindings(&bindings);
-            if !peat_config.dry_run {
-                let script_path = script_name_gen.next();
-                let body_resolved = substitute::substitute(&peat_code.body, &bindings)?;
-                match sh::run_sh_script(script_path.as_path(), &body_resolved) {
-                    Ok(_) => { println!("Process completed successfully.") }
-                    Err(error) => { eprintln!("Process failed: {}", error) }
-                }
+        let directives =
+            Directives::evaluate(&peat_code.directives,
+                                 &code::types::bindings_from_entries(&overrides))?;
+        let n_jobs_max = peat_config.jobs.or(directives.jobs).unwrap_or(1).max(1);
+        let settings = get_job_settings(&peat_config, &directives)?;
+        let n_jobs =
+            if peat_config.dry_run {
+                None
+            } else {
+                evaluate::count_jobs(&peat_code, &overrides, evaluate::MAX_COUNTED_BINDINGS).ok()
+            };
+        if let Some(n_jobs) = n_jobs {
+            verbose!("Counted {} jobs.", n_jobs);
+        }
+        let bindings_iter = evaluate::evaluate_declarations(&peat_code, &overrides);
+        info!("Now evaluating");
+        let summary = runner::run_jobs(&peat_config, &peat_code, bindings_iter, n_jobs,
+                                       n_jobs_max, settings)?;
+        if !peat_config.dry_run {
+            summary.print();
+            if let Some(signal) = signals::interrupt() {
+                return Err(Error::from(format!("Interrupted by signal {}.", signal)));
             }
+            summary.into_result()?;
         }
     }
     Ok(())
 }
 
-fn print_bindings(bindings: &Bindings) {
-    let bindings_vec = bindings.to_vec();
-    let mut entries_iter = bindings_vec.iter();
-    print!("Bindings: ");
-    if let Some((id, value)) = entries_iter.next() {
-        print!("{} = {}", id, value);
-        for (id, value) in entries_iter {
-            print!(", {} = {}", id, value);
-        }
-        println!()
-    } else {
-        println!("[empty]");
+/// Settings from the command line take precedence over directives in the peat file.
+fn get_job_settings(peat_config: &Config, directives: &Directives)
+                    -> Result<JobSettings, Error> {
+    let interpreter =
+        match peat_config.shell.as_ref().or(directives.interpreter.as_ref()) {
+            Some(command) => Interpreter::parse(command)?,
+            None => Interpreter::default_sh()
+        };
+    let timeout = peat_config.timeout.or(directives.timeout);
+    let retry_policy =
+        RetryPolicy {
+            retries: peat_config.retries,
+            delay: peat_config.retry_delay,
+            exit_codes: peat_config.retry_exit_codes.clone(),
+        };
+    Ok(JobSettings { interpreter, timeout, retry_policy })
+}
+
+/// Counts go to stdout, since they are what was asked for.
+/// Writes the counts to stdout. If stdout is closed early, such as by head, the rest is not
+/// wanted, so that is not an error.
+fn print_counts(peat_config: &Config, peat_code: &PeatCode, overrides: &[(String, Value)])
+                -> Result<(), Error> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let write_result =
+        match &peat_config.count_by {
+            Some(id) => {
+                evaluate::count_jobs_by(peat_code, overrides, id)?.iter().try_for_each(
+                    |(literal, n_jobs)| writeln!(out, "{} = {}: {}", id, literal, n_jobs)
+                )
+            }
+            None => writeln!(out, "{}", evaluate::count_jobs(peat_code, overrides, u64::MAX)?)
+        };
+    match write_result {
+        Err(error) if error.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        write_result => Ok(write_result?)
     }
 }
 
 fn print_declarations(peat_code: &PeatCode) {
-    let mut declaration_iter = peat_code.declarations.iter();
-    print!("Declarations: ");
-    if let Some(declaration) = declaration_iter.next() {
-        print!("{}", declaration);
-        for declaration in declaration_iter {
-            print!(", {}", declaration);
-        }
-        println!();
+    if peat_code.declarations.is_empty() {
+        info!("Declarations: [none]");
     } else {
-        println!("[none]");
+        let declarations_strings: Vec<String> =
+            peat_code.declarations.iter().map(|declaration| declaration.to_string()).collect();
+        info!("Declarations: {}", declarations_strings.join(", "));
     }
-}
\ No newline at end of file
+}
+
+fn print_directives(peat_code: &PeatCode) {
+    let directives_strings: Vec<String> =
+        peat_code.directives.iter().map(|directive| {
+            format!("{}{} = {}", code::parse::DIRECTIVE_LINE_START, directive.id,
+                    directive.expression)
+        }).collect();
+    info!("Directives: {}", directives_strings.join(", "));
+}
@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use crate::code::types::Bindings;
+use crate::code::template::Template;
+use crate::util::error::Error;
+
+pub(crate) struct LogFiles {
+    pub(crate) stdout: PathBuf,
+    pub(crate) stderr: PathBuf,
+    /// Whether to append to the files rather than replace them, such as when retrying a job.
+    pub(crate) append: bool,
+}
+
+/// Stands in for the file name in errors in the template for names of log files.
+const LOG_NAME_ORIGIN: &str = "--log-name";
+
+fn parse_log_name(log_name: &str) -> Result<Template, Error> {
+    Template::parse_in_file(log_name, LOG_NAME_ORIGIN, 1)
+}
+
+/// Checks that the template for names of log files only refers to the given variables, so that
+/// a typo fails before the first job runs.
+pub(crate) fn check_log_name(log_name: &str, ids: &[String], str_ids: &[String])
+                             -> Result<(), Error> {
+    parse_log_name(log_name)?.check_ids(ids, str_ids, log_name, LOG_NAME_ORIGIN, 1)
+}
+
+/// Names the files that capture the output of each job.
+pub(crate) struct LogNamer {
+    dir: PathBuf,
+    template: Option<Template>,
+    /// Names given so far, since two jobs with the same name would overwrite each other's files.
+    names: HashSet<String>,
+}
+
+impl LogNamer {
+    pub(crate) fn new(dir: &str, template: Option<String>) -> Result<LogNamer, Error> {
+        fs::create_dir_all(dir).map_err(|error| {
+            Error::from(format!("Could not create log directory {}: {}", dir, error))
+        })?;
+        let template = template.as_deref().map(parse_log_name).transpose()?;
+        Ok(LogNamer { dir: PathBuf::from(dir), template, names: HashSet::new() })
+    }
+
+    pub(crate) fn files(&mut self, index: u64, bindings: &Bindings) -> Result<LogFiles, Error> {
+        let name = match &self.template {
+            Some(template) => template.render(bindings)?,
+            None => format!("job_{}", index)
+        };
+        if !self.names.insert(name.clone()) {
+            return Err(Error::from(format!(
+                "Log files of job {} would be named {} like those of an earlier job, and overwrite \
+                them. Use a --log-name with placeholders that tell all jobs apart.", index, name
+            )));
+        }
+        let stdout = self.dir.join(format!("{}.out", name));
+        let stderr = self.dir.join(format!("{}.err", name));
+        if let Some(parent) = stdout.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(LogFiles { stdout, stderr, append: false })
+    }
+}
@@ -0,0 +1,150 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use crate::code::value::Value;
+use crate::logs::LogFiles;
+use crate::retry;
+use crate::retry::RetryPolicy;
+use crate::sh;
+use crate::util::duration;
+use crate::util::error::Error;
+use crate::util::log::{warning, verbose, debug};
+use crate::sh::{Interpreter, JobFailure, Output};
+
+pub(crate) struct Job {
+    pub(crate) index: u64,
+    pub(crate) bindings: Vec<(String, Value)>,
+    pub(crate) script_path: PathBuf,
+    pub(crate) script: String,
+    pub(crate) log_files: Option<LogFiles>,
+    /// Environment variables to set for the job, in addition to those of peat.
+    pub(crate) env: Vec<(String, String)>,
+}
+
+pub(crate) struct JobResult {
+    pub(crate) index: u64,
+    pub(crate) bindings: Vec<(String, Value)>,
+    pub(crate) result: Result<(), JobFailure>,
+    pub(crate) n_attempts: u32,
+    pub(crate) script_path: PathBuf,
+    pub(crate) log_files: Option<LogFiles>,
+    /// When the last attempt started, so that waiting before a retry does not count.
+    pub(crate) started: SystemTime,
+    pub(crate) finished: SystemTime,
+}
+
+/// How to run each job.
+#[derive(Clone)]
+pub(crate) struct JobSettings {
+    pub(crate) interpreter: Interpreter,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) retry_policy: RetryPolicy,
+}
+
+pub(crate) struct Pool {
+    size: u64,
+    settings: JobSettings,
+    n_running: u64,
+    sender: Sender<JobResult>,
+    receiver: Receiver<JobResult>,
+}
+
+fn job_label(index: u64) -> String {
+    format!("[job {}] ", index)
+}
+
+impl Pool {
+    pub(crate) fn new(size: u64, settings: JobSettings) -> Pool {
+        let (sender, receiver) = mpsc::channel();
+        Pool { size, settings, n_running: 0, sender, receiver }
+    }
+
+    pub(crate) fn is_parallel(&self) -> bool { self.size > 1 }
+
+    /// Prefix for lines about a job, which is empty unless jobs run in parallel.
+    pub(crate) fn label(&self, index: u64) -> String {
+        if self.is_parallel() { job_label(index) } else { String::new() }
+    }
+
+    pub(crate) fn is_full(&self) -> bool { self.n_running >= self.size }
+
+    pub(crate) fn start(&mut self, mut job: Job) {
+        let sender = self.sender.clone();
+        let mut output =
+            match job.log_files.take() {
+                Some(log_files) => Output::Files(log_files),
+                None if self.is_parallel() => Output::Prefix(job_label(job.index)),
+                None => Output::Inherit
+            };
+        let settings = self.settings.clone();
+        let label = self.label(job.index);
+        verbose!("{}Running {} with {}.", label, job.script_path.display(), settings.interpreter);
+        debug!("{}Script:\n{}", label, job.script.trim_end());
+        thread::spawn(move || {
+            let mut started = SystemTime::now();
+            let mut attempt: u32 = 1;
+            let run_result = panic::catch_unwind(AssertUnwindSafe(|| loop {
+                started = SystemTime::now();
+                let result =
+                    sh::run_script(job.script_path.as_path(), &job.script, &job.env,
+                                   &settings.interpreter, &output, settings.timeout);
+                match result {
+                    Err(failure) if settings.retry_policy.should_retry(&failure, attempt) => {
+                        let delay = settings.retry_policy.delay_after(attempt);
+                        warning!("{}Attempt {} of {} failed: {} Retrying in {}.", label, attempt,
+                                  settings.retry_policy.n_attempts_max(), failure,
+                                  duration::format_duration(delay));
+                        if !retry::sleep_unless_interrupted(delay) {
+                            break Err(failure);
+                        }
+                        if let Output::Files(log_files) = &mut output {
+                            log_files.append = true;
+                        }
+                        attempt += 1;
+                    }
+                    result => break result
+                }
+            }));
+            let result =
+                run_result.unwrap_or_else(|_| {
+                    Err(JobFailure::Error(Error::from("Job failed, because peat panicked.")))
+                });
+            let finished = SystemTime::now();
+            let log_files = match output {
+                Output::Files(log_files) => Some(log_files),
+                _ => None
+            };
+            let _ = sender.send(JobResult {
+                index: job.index, bindings: job.bindings, result, n_attempts: attempt,
+                script_path: job.script_path, log_files, started, finished,
+            });
+        });
+        self.n_running += 1;
+    }
+
+    /// Returns a job that has finished, if any, without waiting.
+    pub(crate) fn next_finished(&mut self) -> Option<JobResult> {
+        let job_result = self.receiver.try_recv().ok()?;
+        self.n_running -= 1;
+        Some(job_result)
+    }
+
+    pub(crate) fn is_idle(&self) -> bool { self.n_running == 0 }
+
+    /// Waits up to the given time for the next job to finish, and returns None if none did.
+    pub(crate) fn wait_for_next(&mut self, timeout: Duration) -> Result<Option<JobResult>, Error> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(job_result) => {
+                self.n_running -= 1;
+                Ok(Some(job_result))
+            }
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(Error::from(format!("Lost track of {} running jobs.", self.n_running)))
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::summary::Summary;
+use crate::util::duration;
+use crate::util::error::Error;
+use crate::util::log::info;
+
+/// Tells how far along the jobs are, once per interval on the console, and after each job in the
+/// status file, if any.
+pub(crate) struct Progress {
+    n_jobs: Option<u64>,
+    started: Instant,
+    interval: Duration,
+    last_printed: Instant,
+    status_file: Option<PathBuf>,
+}
+
+impl Progress {
+    pub(crate) fn new(n_jobs: Option<u64>, interval: Duration, status_file: Option<&str>)
+                      -> Progress {
+        let started = Instant::now();
+        let status_file = status_file.map(PathBuf::from);
+        Progress { n_jobs, started, interval, last_printed: started, status_file }
+    }
+
+    /// Estimated time until all jobs are done, from how long the jobs run so far took. Skipped
+    /// jobs take no time, so they are not counted.
+    fn eta(&self, summary: &Summary) -> Option<Duration> {
+        let n_remaining = self.n_jobs?.checked_sub(summary.n_done())?;
+        let n_run = summary.n_done() - summary.n_skipped();
+        if n_run == 0 || n_remaining == 0 {
+            return None;
+        }
+        let secs = self.started.elapsed().as_secs_f64() * (n_remaining as f64) / (n_run as f64);
+        Some(Duration::from_secs_f64(secs))
+    }
+
+    fn line(&self, summary: &Summary) -> String {
+        let mut line =
+            match self.n_jobs {
+                Some(n_jobs) => format!("{}/{} done", summary.n_done(), n_jobs),
+                None => format!("{} done", summary.n_done())
+            };
+        line.push_str(&format!(", {} failed", summary.n_failed()));
+        if let Some(eta) = self.eta(summary) {
+            line.push_str(&format!(", ETA {}", duration::format_duration(eta)));
+        }
+        line
+    }
+
+    /// Replaces the status file in one step, so that whoever polls it never reads half a line.
+    fn write_status_file(&self, status_file: &Path, line: &str) -> Result<(), Error> {
+        let mut tmp_file = status_file.as_os_str().to_os_string();
+        tmp_file.push(".tmp");
+        fs::write(&tmp_file, format!("{}\n", line))
+            .and_then(|_| fs::rename(&tmp_file, status_file))
+            .map_err(|error| {
+                Error::from(format!("Could not write status file {}: {}", status_file.display(),
+                                    error))
+            })
+    }
+
+    /// How long until the next progress line is due on the console.
+    pub(crate) fn until_next_print(&self) -> Duration {
+        self.interval.saturating_sub(self.last_printed.elapsed())
+    }
+
+    /// Prints a progress line if one is due, even if no job finished since the last one, so
+    /// that long jobs do not leave the console silent.
+    pub(crate) fn print_if_due(&mut self, summary: &Summary) {
+        if self.last_printed.elapsed() >= self.interval {
+            info!("Progress: {}", self.line(summary));
+            self.last_printed = Instant::now();
+        }
+    }
+
+    pub(crate) fn update(&mut self, summary: &Summary) -> Result<(), Error> {
+        self.print_if_due(summary);
+        match &self.status_file {
+            Some(status_file) => self.write_status_file(status_file, &self.line(summary)),
+            None => Ok(())
+        }
+    }
+}
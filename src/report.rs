@@ -0,0 +1,161 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::code::value::Value;
+use crate::json::{json_string, json_object, json_opt, bindings_json, NULL};
+use crate::pool::JobResult;
+use crate::sh::JobFailure;
+use crate::summary::Summary;
+use crate::util::error::Error;
+
+mod statuses {
+    pub(crate) const SUCCEEDED: &str = "succeeded";
+    pub(crate) const FAILED: &str = "failed";
+    pub(crate) const TIMED_OUT: &str = "timed-out";
+    pub(crate) const SKIPPED: &str = "skipped";
+}
+
+/// Formats a time as UTC in RFC 3339, e.g. 2021-03-04T05:06:07.089Z.
+fn time_json(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO);
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let secs_of_day = secs % 86400;
+    json_string(&format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z", year, month, day,
+                         secs_of_day / 3600, (secs_of_day / 60) % 60, secs_of_day % 60,
+                         since_epoch.subsec_millis()))
+}
+
+/// Year, month and day of a number of days since 1970-01-01, in the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+        - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn path_json(path: &Path) -> String { json_string(&path.to_string_lossy()) }
+
+/// The directory next to the report where the scripts of the jobs are kept, so that the report
+/// does not name scripts that are gone once Peat is done.
+pub(crate) fn script_dir(report_file: &str) -> String { format!("{}.scripts", report_file) }
+
+/// Writes one JSON object per line for each job, and a summary at the end.
+pub(crate) struct Report {
+    writer: BufWriter<File>,
+}
+
+impl Report {
+    pub(crate) fn create(path: &str) -> Result<Report, Error> {
+        let file = File::create(path).map_err(|error| {
+            Error::from(format!("Could not create report {}: {}", path, error))
+        })?;
+        Ok(Report { writer: BufWriter::new(file) })
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), Error> {
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub(crate) fn record(&mut self, job_result: &JobResult) -> Result<(), Error> {
+        let (status, exit_code, signal, error) =
+            match &job_result.result {
+                Ok(_) => (statuses::SUCCEEDED, Some(0), None, None),
+                Err(JobFailure::ExitCode(code)) => (statuses::FAILED, Some(*code), None, None),
+                Err(JobFailure::Signal(signal)) => (statuses::FAILED, None, Some(*signal), None),
+                Err(JobFailure::TimedOut(_)) => (statuses::TIMED_OUT, None, None, None),
+                Err(JobFailure::Error(error)) => {
+                    (statuses::FAILED, None, None, Some(error.to_string()))
+                }
+            };
+        let duration =
+            job_result.finished.duration_since(job_result.started).unwrap_or(Duration::ZERO);
+        let log_files = job_result.log_files.as_ref();
+        let line = json_object(&[
+            ("type", json_string("job")),
+            ("index", job_result.index.to_string()),
+            ("bindings", bindings_json(&job_result.bindings)),
+            ("status", json_string(status)),
+            ("script", path_json(&job_result.script_path)),
+            ("start_time", time_json(job_result.started)),
+            ("end_time", time_json(job_result.finished)),
+            ("duration_secs", format!("{:.3}", duration.as_secs_f64())),
+            ("exit_code", json_opt(exit_code, |code| code.to_string())),
+            ("signal", json_opt(signal, |signal| signal.to_string())),
+            ("error", json_opt(error.as_deref(), json_string)),
+            ("attempts", job_result.n_attempts.to_string()),
+            ("stdout_log", json_opt(log_files, |log_files| path_json(&log_files.stdout))),
+            ("stderr_log", json_opt(log_files, |log_files| path_json(&log_files.stderr))),
+        ]);
+        self.write_line(&line)
+    }
+
+    pub(crate) fn record_skipped(&mut self, index: u64, bindings: &[(String, Value)])
+                                 -> Result<(), Error> {
+        let null = || String::from(NULL);
+        let line = json_object(&[
+            ("type", json_string("job")),
+            ("index", index.to_string()),
+            ("bindings", bindings_json(bindings)),
+            ("status", json_string(statuses::SKIPPED)),
+            ("script", null()),
+            ("start_time", null()),
+            ("end_time", null()),
+            ("duration_secs", null()),
+            ("exit_code", null()),
+            ("signal", null()),
+            ("error", null()),
+            ("attempts", "0".to_string()),
+            ("stdout_log", null()),
+            ("stderr_log", null()),
+        ]);
+        self.write_line(&line)
+    }
+
+    pub(crate) fn finish(mut self, summary: &Summary, interrupt: Option<i32>)
+                         -> Result<(), Error> {
+        let line = json_object(&[
+            ("type", json_string("summary")),
+            ("succeeded", summary.n_succeeded().to_string()),
+            ("succeeded_after_retry", summary.n_succeeded_after_retry().to_string()),
+            ("failed", (summary.n_failed() - summary.n_timed_out()).to_string()),
+            ("timed_out", summary.n_timed_out().to_string()),
+            ("skipped", summary.n_skipped().to_string()),
+            ("interrupted_by_signal", json_opt(interrupt, |signal| signal.to_string())),
+        ]);
+        self.write_line(&line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+    use super::{civil_from_days, time_json};
+
+    #[test]
+    fn days_map_to_dates_across_leap_years() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(11017), (2000, 3, 1));
+        assert_eq!(civil_from_days(19782), (2024, 2, 29));
+        assert_eq!(civil_from_days(47541), (2100, 3, 1));
+    }
+
+    #[test]
+    fn times_are_written_in_utc_with_milliseconds() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_614_834_367_089);
+        assert_eq!(time_json(time), "\"2021-03-04T05:06:07.089Z\"");
+    }
+}
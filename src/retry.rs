@@ -0,0 +1,87 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::sh::JobFailure;
+use crate::signals;
+
+const SLEEP_INTERVAL: Duration = Duration::from_millis(100);
+
+/// When and how often to run a failed job again.
+#[derive(Clone)]
+pub(crate) struct RetryPolicy {
+    pub(crate) retries: u32,
+    pub(crate) delay: Duration,
+    /// If given, only failures with one of these exit codes are retried.
+    pub(crate) exit_codes: Option<Vec<i32>>,
+}
+
+impl RetryPolicy {
+    pub(crate) fn n_attempts_max(&self) -> u32 { self.retries.saturating_add(1) }
+
+    pub(crate) fn should_retry(&self, failure: &JobFailure, attempt: u32) -> bool {
+        if attempt >= self.n_attempts_max() || signals::interrupt().is_some() {
+            return false;
+        }
+        match (&self.exit_codes, failure) {
+            (None, _) => true,
+            (Some(exit_codes), JobFailure::ExitCode(code)) => exit_codes.contains(code),
+            (Some(_), _) => false
+        }
+    }
+
+    /// The delay after the given attempt, doubling with each attempt.
+    pub(crate) fn delay_after(&self, attempt: u32) -> Duration {
+        let factor = 2u32.checked_pow(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.delay.checked_mul(factor).unwrap_or(Duration::MAX)
+    }
+}
+
+/// Sleeps for the given duration, but returns false early if peat is interrupted.
+pub(crate) fn sleep_unless_interrupted(duration: Duration) -> bool {
+    let deadline = Instant::now().checked_add(duration);
+    loop {
+        if signals::interrupt().is_some() {
+            return false;
+        }
+        let now = Instant::now();
+        match deadline {
+            Some(deadline) if now >= deadline => return true,
+            Some(deadline) => thread::sleep(SLEEP_INTERVAL.min(deadline - now)),
+            None => thread::sleep(SLEEP_INTERVAL)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::sh::JobFailure;
+    use super::RetryPolicy;
+
+    fn policy(retries: u32, exit_codes: Option<Vec<i32>>) -> RetryPolicy {
+        RetryPolicy { retries, delay: Duration::from_secs(3), exit_codes }
+    }
+
+    #[test]
+    fn delay_doubles_with_each_attempt() {
+        let policy = policy(5, None);
+        assert_eq!(policy.delay_after(1), Duration::from_secs(3));
+        assert_eq!(policy.delay_after(2), Duration::from_secs(6));
+        assert_eq!(policy.delay_after(4), Duration::from_secs(24));
+    }
+
+    #[test]
+    fn delay_after_many_attempts_does_not_overflow() {
+        let longest = Duration::from_secs(3) * u32::MAX;
+        assert_eq!(policy(u32::MAX, None).delay_after(40), longest);
+        assert_eq!(policy(u32::MAX, None).delay_after(u32::MAX), longest);
+    }
+
+    #[test]
+    fn only_listed_exit_codes_are_retried_up_to_the_limit() {
+        let policy = policy(2, Some(vec![75]));
+        assert!(policy.should_retry(&JobFailure::ExitCode(75), 2));
+        assert!(!policy.should_retry(&JobFailure::ExitCode(75), 3));
+        assert!(!policy.should_retry(&JobFailure::ExitCode(1), 1));
+        assert!(!policy.should_retry(&JobFailure::TimedOut(Duration::from_secs(1)), 1));
+    }
+}
@@ -0,0 +1,201 @@
+use std::path::Path;
+use crate::code::PeatCode;
+use crate::code::types::{self, BindingsIterator};
+use crate::code::value::Value;
+use crate::config::Config;
+use crate::job_env;
+use crate::logs::LogNamer;
+use crate::pool::{Job, JobResult, JobSettings, Pool};
+use crate::progress::Progress;
+use crate::report::{self, Report};
+use crate::script_files::ScriptNameGenerator;
+use crate::signals;
+use crate::state::Journal;
+use crate::summary::Summary;
+use crate::util::error::Error;
+use crate::util::log::{self, info, log_at, warning};
+
+struct Runner<'a> {
+    config: &'a Config,
+    n_jobs: Option<u64>,
+    pool: Pool,
+    summary: Summary,
+    journal: Option<Journal>,
+    log_namer: Option<LogNamer>,
+    report: Option<Report>,
+    progress: Progress,
+}
+
+impl<'a> Runner<'a> {
+    fn new(config: &'a Config, n_jobs: Option<u64>, n_jobs_max: u64, settings: JobSettings)
+           -> Result<Runner<'a>, Error> {
+        let journal = match &config.state_dir {
+            Some(state_dir) => {
+                let journal = Journal::open(Path::new(state_dir))?;
+                info!("Journal in {} lists {} jobs that already succeeded.", state_dir,
+                         journal.n_succeeded());
+                Some(journal)
+            }
+            None => None
+        };
+        let log_namer = match &config.log_dir {
+            Some(log_dir) if !config.dry_run => {
+                info!("Writing output of jobs to {}.", log_dir);
+                Some(LogNamer::new(log_dir, config.log_name.clone())?)
+            }
+            _ => None
+        };
+        let report = match &config.report {
+            Some(report_file) if !config.dry_run => Some(Report::create(report_file)?),
+            _ => None
+        };
+        let pool = Pool::new(n_jobs_max, settings);
+        let summary = Summary::new(job_level(config));
+        let mut progress =
+            Progress::new(n_jobs, config.progress_interval, config.status_file.as_deref());
+        if !config.dry_run {
+            progress.update(&summary)?;
+        }
+        Ok(Runner { config, n_jobs, pool, summary, journal, log_namer, report, progress })
+    }
+
+    fn record(&mut self, job_result: JobResult) -> Result<(), Error> {
+        let journal_result = match &mut self.journal {
+            Some(journal) => journal.record(&job_result),
+            None => Ok(())
+        };
+        let report_result = match &mut self.report {
+            Some(report) => report.record(&job_result),
+            None => Ok(())
+        };
+        self.summary.record(&self.pool.label(job_result.index), job_result);
+        let progress_result = self.progress.update(&self.summary);
+        journal_result.and(report_result).and(progress_result)
+    }
+
+    fn is_failure_limit_reached(&self) -> bool {
+        match self.config.max_failures {
+            Some(max_failures) => self.summary.n_failed() >= max_failures,
+            None => false
+        }
+    }
+
+    fn has_succeeded_before(&self, bindings: &[(String, Value)]) -> bool {
+        match &self.journal {
+            Some(journal) => journal.has_succeeded(bindings),
+            None => false
+        }
+    }
+
+    /// Waits for the next job to finish, or returns None if no job is running. Meanwhile, it
+    /// prints progress when it is due.
+    fn wait_for_next(&mut self) -> Result<Option<JobResult>, Error> {
+        while !self.pool.is_idle() {
+            if let Some(job_result) = self.pool.wait_for_next(self.progress.until_next_print())? {
+                return Ok(Some(job_result));
+            }
+            self.progress.print_if_due(&self.summary);
+        }
+        Ok(None)
+    }
+
+    fn start_all(&mut self, peat_code: &PeatCode, bindings_iter: BindingsIterator,
+                 script_name_gen: &ScriptNameGenerator) -> Result<(), Error> {
+        for (index, bindings_result) in bindings_iter.enumerate() {
+            let index = index as u64;
+            let bindings = bindings_result?;
+            while let Some(job_result) = self.pool.next_finished() {
+                self.record(job_result)?;
+            }
+            if !self.config.dry_run && self.pool.is_full() {
+                if let Some(job_result) = self.wait_for_next()? {
+                    self.record(job_result)?;
+                }
+            }
+            if let Some(signal) = signals::interrupt() {
+                warning!("Interrupted by signal {}, not starting any more jobs.", signal);
+                break;
+            }
+            if self.is_failure_limit_reached() {
+                warning!("Reached limit of {} failed jobs, not starting any more jobs.",
+                          self.summary.n_failed());
+                break;
+            }
+            let label = self.pool.label(index);
+            log_at!(job_level(self.config), "{}Bindings: {}", label,
+                    types::format_bindings(&bindings.to_vec()));
+            let bindings_vec = bindings.to_vec();
+            if self.has_succeeded_before(&bindings_vec) {
+                log_at!(job_level(self.config), "{}Skipping job, because it already succeeded.",
+                        label);
+                self.summary.record_skipped();
+                if let Some(report) = &mut self.report {
+                    report.record_skipped(index, &bindings_vec)?;
+                }
+                self.progress.update(&self.summary)?;
+                continue;
+            }
+            if !self.config.dry_run {
+                let script = peat_code.template.render(&bindings)?;
+                let script_path = script_name_gen.path(index);
+                let log_files =
+                    self.log_namer.as_mut().map(|log_namer| {
+                        log_namer.files(index, &bindings)
+                    }).transpose()?;
+                let env =
+                    if self.config.export_env {
+                        job_env::job_env(index, self.n_jobs, &bindings_vec)
+                    } else {
+                        Vec::new()
+                    };
+                self.pool.start(Job { index, bindings: bindings_vec, script_path, script,
+                                      log_files, env });
+            }
+        }
+        Ok(())
+    }
+
+    fn wait_for_all(&mut self) -> Result<(), Error> {
+        let mut result: Result<(), Error> = Ok(());
+        while let Some(job_result) = self.wait_for_next()? {
+            let record_result = self.record(job_result);
+            if result.is_ok() {
+                result = record_result;
+            }
+        }
+        result
+    }
+}
+
+pub(crate) fn run_jobs(config: &Config, peat_code: &PeatCode, bindings_iter: BindingsIterator,
+                       n_jobs: Option<u64>, n_jobs_max: u64, settings: JobSettings)
+                       -> Result<Summary, Error> {
+    if !config.dry_run {
+        signals::catch_interrupts();
+    }
+    let extension = settings.interpreter.script_extension();
+    let mut runner = Runner::new(config, n_jobs, n_jobs_max, settings)?;
+    let script_name_gen =
+        match &config.report {
+            Some(report_file) if !config.dry_run => {
+                ScriptNameGenerator::in_kept_dir(&report::script_dir(report_file), extension)?
+            }
+            _ => ScriptNameGenerator::from_temp_dir(extension)?
+        };
+    let start_result = runner.start_all(peat_code, bindings_iter, &script_name_gen);
+    let wait_result = runner.wait_for_all();
+    let report_result = match runner.report.take() {
+        Some(report) => report.finish(&runner.summary, signals::interrupt()),
+        None => Ok(())
+    };
+    start_result?;
+    wait_result?;
+    report_result?;
+    Ok(runner.summary)
+}
+
+/// Level at which to say which job runs and that it succeeded. With a log directory, the
+/// console only shows progress, failures and the summary, unless peat is asked to say more.
+fn job_level(config: &Config) -> log::Level {
+    if config.log_dir.is_some() { log::Level::Verbose } else { log::Level::Normal }
+}
@@ -1,34 +1,67 @@
+use std::fs;
 use std::path::PathBuf;
 use crate::util::error::Error;
+use crate::util::log::warning;
+
+/// Names a script after the index of its job, like script7.sh.
+fn script_name(index: u64, extension: &str) -> String { format!("script{}.{}", index, extension) }
+
+/// Whether a file looks like a script from script_name, with any extension.
+fn is_script_name(name: &str) -> bool {
+    match name.strip_prefix("script").and_then(|rest| rest.split_once('.')) {
+        Some((index, _)) => !index.is_empty() && index.chars().all(|ch| ch.is_ascii_digit()),
+        None => false
+    }
+}
 
 pub(crate) struct ScriptNameGenerator {
     dir: PathBuf,
-    counter: u32,
+    extension: &'static str,
+    /// Whether to keep the scripts when done, such as for a report that names them.
+    is_kept: bool,
 }
 
 impl ScriptNameGenerator {
-    pub(crate) fn from_temp_dir() -> Result<ScriptNameGenerator, Error> {
-        Ok(ScriptNameGenerator::new(tempfile::tempdir()?.into_path()))
+    pub(crate) fn from_temp_dir(extension: &'static str) -> Result<ScriptNameGenerator, Error> {
+        Ok(ScriptNameGenerator::new(tempfile::tempdir()?.into_path(), extension, false))
     }
-    pub(crate) fn new(dir: PathBuf) -> ScriptNameGenerator {
-        let counter = 0u32;
-        ScriptNameGenerator { dir, counter }
+    /// Writes the scripts to the given directory, and keeps them when done. Scripts of an
+    /// earlier run in the directory are removed first, so that all scripts are from this run.
+    pub(crate) fn in_kept_dir(dir: &str, extension: &'static str)
+                              -> Result<ScriptNameGenerator, Error> {
+        let dir_error = |error: std::io::Error| {
+            Error::from(format!("Could not prepare script directory {}: {}", dir, error))
+        };
+        fs::create_dir_all(dir).map_err(dir_error)?;
+        for entry in fs::read_dir(dir).map_err(dir_error)? {
+            let entry = entry.map_err(dir_error)?;
+            if entry.file_type().map_err(dir_error)?.is_file() &&
+                is_script_name(&entry.file_name().to_string_lossy()) {
+                fs::remove_file(entry.path()).map_err(dir_error)?;
+            }
+        }
+        let dir = fs::canonicalize(dir).map_err(dir_error)?;
+        Ok(ScriptNameGenerator::new(dir, extension, true))
+    }
+    pub(crate) fn new(dir: PathBuf, extension: &'static str, is_kept: bool)
+                      -> ScriptNameGenerator {
+        ScriptNameGenerator { dir, extension, is_kept }
     }
 
-    pub(crate) fn next(&mut self) -> PathBuf {
-        let mut script_path_buf = self.dir.clone();
-        script_path_buf.push(format!("script{}.sh", self.counter));
-        self.counter += 1;
-        script_path_buf
+    pub(crate) fn path(&self, index: u64) -> PathBuf {
+        self.dir.join(script_name(index, self.extension))
     }
 }
 
 impl Drop for ScriptNameGenerator {
     fn drop(&mut self) {
+        if self.is_kept {
+            return;
+        }
         match std::fs::remove_dir_all(&self.dir) {
             Ok(_) => {}
             Err(error) => {
-                println!("Failed to remove directory {}: {}",
+                warning!("Failed to remove directory {}: {}",
                          self.dir.to_str().unwrap_or("<unprintable>"), error)
             }
         }
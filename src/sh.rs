@@ -1,22 +1,243 @@
-use std::fs::File;
-use std::io::Write;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use crate::logs::LogFiles;
 use crate::util::error::Error;
-use std::process::Command;
+use crate::signals;
+use crate::util::duration;
+use std::fmt::{Display, Formatter};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::path::Path;
+use std::time::{Duration, Instant};
+use std::{fmt, io, thread};
+use std::thread::JoinHandle;
 
-pub(crate) fn run_sh_script(script_path: &Path, content: &str) -> Result<(), Error> {
+/// Where the output of a job goes.
+pub(crate) enum Output {
+    Inherit,
+    /// To the console, with each line prefixed.
+    Prefix(String),
+    Files(LogFiles),
+}
+
+/// The program that runs the scripts, with arguments to put before the script path.
+#[derive(Clone)]
+pub(crate) struct Interpreter {
+    program: String,
+    args: Vec<String>,
+}
+
+const DEFAULT_INTERPRETER: &str = "sh";
+
+impl Interpreter {
+    /// Parses a command like `/bin/bash -euo pipefail`, split at whitespace.
+    pub(crate) fn parse(command: &str) -> Result<Interpreter, Error> {
+        let mut words = command.split_whitespace().map(String::from);
+        let program =
+            words.next().ok_or_else(|| Error::from("Interpreter command is empty."))?;
+        let args = words.collect();
+        Ok(Interpreter { program, args })
+    }
+
+    pub(crate) fn default_sh() -> Interpreter {
+        Interpreter { program: String::from(DEFAULT_INTERPRETER), args: Vec::new() }
+    }
+
+    /// File extension for scripts, since some interpreters care about it.
+    pub(crate) fn script_extension(&self) -> &'static str {
+        let name = self.program.rsplit('/').next().unwrap_or(&self.program);
+        if name.starts_with("python") {
+            "py"
+        } else if name == "Rscript" || name == "R" {
+            "R"
+        } else if name.starts_with("perl") {
+            "pl"
+        } else if name.starts_with("ruby") {
+            "rb"
+        } else if name.starts_with("node") {
+            "js"
+        } else {
+            "sh"
+        }
+    }
+}
+
+impl Display for Interpreter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.program)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+fn create_log_file(path: &Path, append: bool) -> Result<File, Error> {
+    let mut options = OpenOptions::new();
+    if append { options.append(true) } else { options.write(true).truncate(true) };
+    options.create(true).open(path).map_err(|error| {
+        Error::from(format!("Could not create log file {}: {}", path.display(), error))
+    })
+}
+
+/// Why a job did not complete successfully.
+pub(crate) enum JobFailure {
+    ExitCode(i32),
+    Signal(i32),
+    TimedOut(Duration),
+    Error(Error),
+}
+
+impl From<io::Error> for JobFailure {
+    fn from(io_error: io::Error) -> Self { JobFailure::Error(Error::from(io_error)) }
+}
+
+impl From<Error> for JobFailure {
+    fn from(error: Error) -> Self { JobFailure::Error(error) }
+}
+
+impl Display for JobFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            JobFailure::ExitCode(code) => write!(f, "Process failed with exit code {}.", code),
+            JobFailure::Signal(signal) => write!(f, "Process was killed by signal {}.", signal),
+            JobFailure::TimedOut(timeout) => {
+                write!(f, "Process timed out after {}.", duration::format_duration(*timeout))
+            }
+            JobFailure::Error(error) => Display::fmt(error, f)
+        }
+    }
+}
+
+/// How long a job has to exit after SIGTERM before it gets SIGKILL.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Waits until the process exits, the deadline passes or peat is interrupted, whichever is first.
+fn wait_until(child: &mut Child, deadline: Option<Instant>) -> io::Result<Option<ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if signals::interrupt().is_some() {
+            return Ok(None);
+        }
+        if let Some(deadline) = deadline {
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// When a timeout that starts now ends, or None if there is no timeout or it ends too far in
+/// the future to tell.
+fn deadline_after(timeout: Option<Duration>) -> Option<Instant> {
+    timeout.and_then(|timeout| Instant::now().checked_add(timeout))
+}
+
+/// Sends the signal to the process group of the job, then waits for the group to exit, and
+/// kills it if it does not exit within the grace period. The process of the job is only reaped
+/// at the end, so that no other process group can take the id of its group before that.
+fn terminate(child: &mut Child, signal: libc::c_int) -> io::Result<ExitStatus> {
+    let pgid = child.id();
+    signals::signal_group(pgid, signal);
+    let deadline = Instant::now() + KILL_GRACE_PERIOD;
+    while signals::is_group_alive(pgid) && Instant::now() < deadline {
+        thread::sleep(POLL_INTERVAL);
+    }
+    if signals::is_group_alive(pgid) {
+        signals::signal_group(pgid, libc::SIGKILL);
+    }
+    child.wait()
+}
+
+pub(crate) fn run_script(script_path: &Path, content: &str, env: &[(String, String)],
+                         interpreter: &Interpreter, output: &Output, timeout: Option<Duration>)
+                         -> Result<(), JobFailure> {
     let mut file = File::create(script_path)?;
     file.write_all(content.as_ref())?;
-    let mut cmd = Command::new("sh");
-    let cmd_with_arg = cmd.arg(script_path);
-    let mut child = cmd_with_arg.spawn()?;
-    let status = child.wait()?;
-    if !status.success() {
-        let status_code =
-            status.code()
-                .ok_or_else(|| Error::from("Process failed and no exit code available."))?;
-        Err(Error::from(format!("Process failed with exit code {}.", status_code)))
-    } else {
-        Ok(())
+    let mut cmd = Command::new(&interpreter.program);
+    let cmd_with_arg =
+        cmd.args(&interpreter.args).arg(script_path).envs(env.iter().cloned()).process_group(0);
+    match output {
+        Output::Inherit => {}
+        Output::Prefix(_) => {
+            cmd_with_arg.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+        Output::Files(log_files) => {
+            cmd_with_arg.stdout(create_log_file(&log_files.stdout, log_files.append)?)
+                .stderr(create_log_file(&log_files.stderr, log_files.append)?);
+        }
+    }
+    let mut child = cmd_with_arg.spawn().map_err(|error| {
+        Error::from(format!("Could not run {}: {}", interpreter.program, error))
+    })?;
+    let mut copiers = Vec::<JoinHandle<()>>::new();
+    if let Output::Prefix(prefix) = output {
+        if let Some(stdout) = child.stdout.take() {
+            copiers.push(copy_with_prefix(stdout, prefix.to_string(), || io::stdout().lock()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            copiers.push(copy_with_prefix(stderr, prefix.to_string(), || io::stderr().lock()));
+        }
+    }
+    let deadline = deadline_after(timeout);
+    let (status, is_timed_out) = match wait_until(&mut child, deadline)? {
+        Some(status) => (status, false),
+        None => match signals::interrupt() {
+            Some(signal) => (terminate(&mut child, signal)?, false),
+            None => (terminate(&mut child, libc::SIGTERM)?, true)
+        }
+    };
+    for copier in copiers {
+        copier.join().map_err(|_| Error::from("Failed to copy output of process."))?;
+    }
+    match (timeout, is_timed_out) {
+        (Some(timeout), true) => Err(JobFailure::TimedOut(timeout)),
+        _ if status.success() => Ok(()),
+        _ => match (status.code(), status.signal()) {
+            (Some(code), _) => Err(JobFailure::ExitCode(code)),
+            (None, Some(signal)) => Err(JobFailure::Signal(signal)),
+            (None, None) => {
+                Err(JobFailure::Error(Error::from("Process failed and no exit code available.")))
+            }
+        }
+    }
+}
+
+fn copy_with_prefix<R, W, F>(source: R, prefix: String, target: F) -> JoinHandle<()>
+    where R: Read + Send + 'static, W: Write, F: Fn() -> W + Send + 'static {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(source);
+        let mut line = Vec::<u8>::new();
+        loop {
+            line.clear();
+            line.extend_from_slice(prefix.as_bytes());
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => { break; }
+                Ok(_) => {
+                    if !line.ends_with(b"\n") {
+                        line.push(b'\n');
+                    }
+                    let _ = target().write_all(&line);
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use super::deadline_after;
+
+    #[test]
+    fn timeout_too_large_for_a_deadline_has_none() {
+        assert!(deadline_after(Some(Duration::MAX)).is_none());
+        assert!(deadline_after(Some(Duration::from_secs(u64::MAX))).is_none());
+        assert!(deadline_after(Some(Duration::from_secs(1))).is_some());
+        assert!(deadline_after(None).is_none());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,65 @@
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// The last interrupt signal peat received, or 0.
+static INTERRUPT: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn on_interrupt(signal: libc::c_int) {
+    INTERRUPT.store(signal, Ordering::SeqCst);
+}
+
+/// Catches SIGINT and SIGTERM instead of exiting, since jobs run in their own process groups and
+/// would not receive them from the terminal. Running jobs get the signal passed on instead.
+pub(crate) fn catch_interrupts() {
+    let handler = on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+/// The interrupt signal peat received, if any.
+pub(crate) fn interrupt() -> Option<libc::c_int> {
+    match INTERRUPT.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal)
+    }
+}
+
+/// Sends a signal to all processes in a process group.
+pub(crate) fn signal_group(pgid: u32, signal: libc::c_int) {
+    unsafe {
+        libc::kill(-(pgid as libc::pid_t), signal);
+    }
+}
+
+/// Whether any process of a process group is still running. Zombies do not count, since in a
+/// container without an init process, nobody may ever reap them. Without /proc, zombies are
+/// counted as well.
+pub(crate) fn is_group_alive(pgid: u32) -> bool {
+    match fs::read_dir("/proc") {
+        Ok(entries) => {
+            entries.flatten().any(|entry| is_running_in_group(&entry.path().join("stat"), pgid))
+        }
+        Err(_) => unsafe { libc::kill(-(pgid as libc::pid_t), 0) == 0 }
+    }
+}
+
+/// Whether the process of a /proc/PID/stat file is in the process group and not a zombie. The
+/// state and group come after the command name, which is in parentheses and may contain spaces.
+fn is_running_in_group(stat_file: &Path, pgid: u32) -> bool {
+    let stat = match fs::read_to_string(stat_file) {
+        Ok(stat) => stat,
+        Err(_) => return false
+    };
+    let fields: Vec<&str> =
+        match stat.rfind(')') {
+            Some(i) => stat[(i + 1)..].split_whitespace().collect(),
+            None => return false
+        };
+    match (fields.first(), fields.get(2)) {
+        (Some(state), Some(group)) => *state != "Z" && group.parse::<u32>() == Ok(pgid),
+        _ => false
+    }
+}
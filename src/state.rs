@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use crate::code::types;
+use crate::code::value::Value;
+use crate::pool::JobResult;
+use crate::sh::JobFailure;
+use crate::util::error::Error;
+
+const JOURNAL_FILE_NAME: &str = "journal.tsv";
+
+mod outcomes {
+    pub(crate) const SUCCEEDED: &str = "succeeded";
+    pub(crate) const FAILED: &str = "failed";
+    pub(crate) const TIMED_OUT: &str = "timed-out";
+}
+
+/// Append-only record of finished jobs, so that a rerun can skip jobs that already succeeded.
+pub(crate) struct Journal {
+    file: File,
+    succeeded: HashSet<String>,
+}
+
+fn bindings_key(bindings: &[(String, Value)]) -> String {
+    types::format_bindings(bindings)
+}
+
+impl Journal {
+    pub(crate) fn open(dir: &Path) -> Result<Journal, Error> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(JOURNAL_FILE_NAME);
+        let mut succeeded = HashSet::<String>::new();
+        let mut ends_with_newline = true;
+        if path.exists() {
+            let mut content = Vec::<u8>::new();
+            File::open(&path)?.read_to_end(&mut content)?;
+            ends_with_newline = content.is_empty() || content.ends_with(b"\n");
+            for line in BufReader::new(content.as_slice()).lines() {
+                if let Some((outcome, key)) = line?.split_once('\t') {
+                    if outcome == outcomes::SUCCEEDED {
+                        succeeded.insert(key.to_string());
+                    }
+                }
+            }
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        if !ends_with_newline {
+            // Terminate a line that was cut short by a crash, so it is not merged with the next.
+            file.write_all(b"\n")?;
+        }
+        Ok(Journal { file, succeeded })
+    }
+
+    pub(crate) fn n_succeeded(&self) -> usize { self.succeeded.len() }
+
+    pub(crate) fn has_succeeded(&self, bindings: &[(String, Value)]) -> bool {
+        self.succeeded.contains(&bindings_key(bindings))
+    }
+
+    pub(crate) fn record(&mut self, job_result: &JobResult) -> Result<(), Error> {
+        let key = bindings_key(&job_result.bindings);
+        let outcome =
+            match &job_result.result {
+                Ok(_) => outcomes::SUCCEEDED,
+                Err(JobFailure::TimedOut(_)) => outcomes::TIMED_OUT,
+                Err(_) => outcomes::FAILED
+            };
+        self.file.write_all(format!("{}\t{}\n", outcome, key).as_bytes())?;
+        self.file.sync_data()?;
+        if job_result.result.is_ok() {
+            self.succeeded.insert(key);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+    use crate::code::value::Value;
+    use crate::pool::JobResult;
+    use crate::sh::JobFailure;
+    use super::{bindings_key, Journal, JOURNAL_FILE_NAME};
+
+    fn job_result(i: u64, result: Result<(), JobFailure>) -> JobResult {
+        JobResult {
+            index: i,
+            bindings: bindings(i),
+            result,
+            n_attempts: 1,
+            script_path: PathBuf::new(),
+            log_files: None,
+            started: SystemTime::now(),
+            finished: SystemTime::now(),
+        }
+    }
+
+    fn bindings(i: u64) -> Vec<(String, Value)> {
+        vec![(String::from("i"), Value::new_int(i)),
+             (String::from("s"), Value::new_str(String::from("a b")))]
+    }
+
+    #[test]
+    fn key_lists_the_bindings_in_order() {
+        assert_eq!(bindings_key(&bindings(7)), "i = 7, s = \"a b\"");
+    }
+
+    #[test]
+    fn only_succeeded_jobs_are_read_back() {
+        let dir = std::env::temp_dir().join(format!("peat-journal-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut journal = Journal::open(&dir).unwrap();
+        journal.record(&job_result(0, Ok(()))).unwrap();
+        journal.record(&job_result(1, Err(JobFailure::ExitCode(1)))).unwrap();
+        drop(journal);
+        // A line cut short by a crash is neither read, nor merged with the next one.
+        let path = dir.join(JOURNAL_FILE_NAME);
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "succ").unwrap();
+        let mut journal = Journal::open(&dir).unwrap();
+        journal.record(&job_result(2, Ok(()))).unwrap();
+        let journal = Journal::open(&dir).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(journal.n_succeeded(), 2);
+        assert!(journal.has_succeeded(&bindings(0)));
+        assert!(!journal.has_succeeded(&bindings(1)));
+        assert!(journal.has_succeeded(&bindings(2)));
+        assert_eq!(content,
+                   "succeeded\ti = 0, s = \"a b\"\nfailed\ti = 1, s = \"a b\"\nsucc\n\
+                   succeeded\ti = 2, s = \"a b\"\n");
+    }
+}
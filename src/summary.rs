@@ -0,0 +1,107 @@
+use crate::code::types;
+use crate::pool::JobResult;
+use crate::sh::JobFailure;
+use crate::util::error::Error;
+use crate::util::log::{self, info, log_at, warning};
+
+struct Failure {
+    index: u64,
+    bindings: String,
+    message: String,
+    is_timed_out: bool,
+}
+
+pub(crate) struct Summary {
+    n_succeeded: u64,
+    n_succeeded_after_retry: u64,
+    n_skipped: u64,
+    failures: Vec<Failure>,
+    /// Level at which to say that a job succeeded.
+    job_level: log::Level,
+}
+
+impl Summary {
+    pub(crate) fn new(job_level: log::Level) -> Summary {
+        Summary {
+            n_succeeded: 0, n_succeeded_after_retry: 0, n_skipped: 0, failures: Vec::new(),
+            job_level,
+        }
+    }
+
+    /// Number of jobs that did not succeed, including those that timed out.
+    pub(crate) fn n_failed(&self) -> u64 { self.failures.len() as u64 }
+
+    pub(crate) fn n_succeeded(&self) -> u64 { self.n_succeeded }
+
+    pub(crate) fn n_succeeded_after_retry(&self) -> u64 { self.n_succeeded_after_retry }
+
+    pub(crate) fn n_skipped(&self) -> u64 { self.n_skipped }
+
+    /// Number of jobs that finished or were skipped.
+    pub(crate) fn n_done(&self) -> u64 { self.n_succeeded + self.n_failed() + self.n_skipped }
+
+    pub(crate) fn n_timed_out(&self) -> u64 {
+        self.failures.iter().filter(|failure| failure.is_timed_out).count() as u64
+    }
+
+    pub(crate) fn record_skipped(&mut self) { self.n_skipped += 1 }
+
+    pub(crate) fn record(&mut self, label: &str, job_result: JobResult) {
+        match job_result.result {
+            Ok(_) if job_result.n_attempts > 1 => {
+                log_at!(self.job_level, "{}Process completed successfully on attempt {}.",
+                        label, job_result.n_attempts);
+                self.n_succeeded += 1;
+                self.n_succeeded_after_retry += 1;
+            }
+            Ok(_) => {
+                log_at!(self.job_level, "{}Process completed successfully.", label);
+                self.n_succeeded += 1;
+            }
+            Err(failure) => {
+                let is_timed_out = matches!(failure, JobFailure::TimedOut(_));
+                if is_timed_out {
+                    warning!("{}{}", label, failure);
+                } else {
+                    warning!("{}Process failed: {}", label, failure);
+                }
+                let index = job_result.index;
+                let bindings = types::format_bindings(&job_result.bindings);
+                let message =
+                    if job_result.n_attempts > 1 {
+                        format!("{} Gave up after {} attempts.", failure, job_result.n_attempts)
+                    } else {
+                        failure.to_string()
+                    };
+                self.failures.push(Failure { index, bindings, message, is_timed_out });
+            }
+        }
+    }
+
+    pub(crate) fn print(&self) {
+        let mut counts = format!("Jobs succeeded: {}", self.n_succeeded);
+        if self.n_succeeded_after_retry > 0 {
+            counts.push_str(&format!(" ({} after retrying)", self.n_succeeded_after_retry));
+        }
+        counts.push_str(&format!(", failed: {}", self.n_failed() - self.n_timed_out()));
+        if self.n_timed_out() > 0 {
+            counts.push_str(&format!(", timed out: {}", self.n_timed_out()));
+        }
+        if self.n_skipped > 0 {
+            counts.push_str(&format!(", skipped: {}", self.n_skipped));
+        }
+        info!("{}", counts);
+        for failure in &self.failures {
+            warning!("Failed job {}: {} ({})", failure.index, failure.bindings, failure.message);
+        }
+    }
+
+    pub(crate) fn into_result(self) -> Result<(), Error> {
+        if self.failures.is_empty() {
+            Ok(())
+        } else {
+            let n_jobs = self.n_succeeded + self.n_failed();
+            Err(Error::from(format!("{} of {} jobs failed.", self.n_failed(), n_jobs)))
+        }
+    }
+}
@@ -1,2 +1,4 @@
 pub mod error;
+pub mod duration;
 pub mod matryoshka;
+pub mod log;
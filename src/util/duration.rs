@@ -0,0 +1,108 @@
+use std::time::Duration;
+use crate::util::error::Error;
+
+const UNITS: [(char, u64); 4] = [('d', 86400), ('h', 3600), ('m', 60), ('s', 1)];
+
+/// Longer durations are rejected, since adding them to the current time could overflow.
+const MAX_SECS: u64 = 36500 * 86400;
+
+/// Parses durations like 90, 45s, 30m, 2h, 1d or 1h30m, where a number without unit is seconds.
+pub fn parse_duration(string: &str) -> Result<Duration, Error> {
+    let invalid = || {
+        Error::from(format!("Expected duration like 90s, 30m, 2h or 1h30m, but got {}.", string))
+    };
+    let too_long = || {
+        Error::from(format!("Duration {} is too long, since it can be at most {}.", string,
+                            format_duration(Duration::from_secs(MAX_SECS))))
+    };
+    let trimmed = string.trim();
+    if trimmed.is_empty() {
+        return Err(invalid());
+    }
+    let mut secs: u64 = 0;
+    let mut number = String::new();
+    let mut has_unit = false;
+    for ch in trimmed.chars() {
+        if ch.is_ascii_digit() {
+            number.push(ch);
+        } else {
+            let unit_secs =
+                UNITS.iter().find(|(unit, _)| *unit == ch).map(|(_, unit_secs)| *unit_secs)
+                    .ok_or_else(invalid)?;
+            if number.is_empty() {
+                return Err(invalid());
+            }
+            let n = number.parse::<u64>().map_err(|_| too_long())?;
+            secs = n.checked_mul(unit_secs).and_then(|n_secs| secs.checked_add(n_secs))
+                .ok_or_else(too_long)?;
+            number.clear();
+            has_unit = true;
+        }
+    }
+    if !number.is_empty() {
+        if has_unit {
+            return Err(invalid());
+        }
+        let n = number.parse::<u64>().map_err(|_| too_long())?;
+        secs = secs.checked_add(n).ok_or_else(too_long)?;
+    }
+    if secs > MAX_SECS {
+        return Err(too_long());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Formats a duration like 1h30m, rounded down to whole seconds.
+pub fn format_duration(duration: Duration) -> String {
+    let mut secs = duration.as_secs();
+    if secs == 0 {
+        return String::from("0s");
+    }
+    let mut string = String::new();
+    for (unit, unit_secs) in UNITS.iter() {
+        if secs >= *unit_secs {
+            string.push_str(&format!("{}{}", secs / unit_secs, unit));
+            secs %= unit_secs;
+        }
+    }
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use super::{format_duration, parse_duration};
+
+    #[test]
+    fn durations_are_parsed_with_or_without_units() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration(" 45s ").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("1d2h3m4s").unwrap(), Duration::from_secs(93784));
+    }
+
+    #[test]
+    fn malformed_durations_are_rejected() {
+        for string in ["", "h", "1x", "1h30", "1.5h", "-1s"] {
+            let error = parse_duration(string).err().unwrap();
+            assert!(error.to_string().contains("Expected duration"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn durations_are_formatted_with_the_largest_units() {
+        assert_eq!(format_duration(Duration::from_millis(999)), "0s");
+        assert_eq!(format_duration(Duration::from_secs(5400)), "1h30m");
+        assert_eq!(format_duration(Duration::from_secs(93784)), "1d2h3m4s");
+        assert_eq!(format_duration(parse_duration("2h").unwrap()), "2h");
+    }
+
+    #[test]
+    fn durations_that_could_overflow_are_rejected() {
+        for string in ["18446744073709551615", "18446744073709551616", "300000000000d", "36501d"] {
+            let error = parse_duration(string).err().unwrap();
+            assert!(error.to_string().contains("too long"), "{}", error);
+        }
+        assert_eq!(parse_duration("36500d").unwrap(), Duration::from_secs(36500 * 86400));
+    }
+}
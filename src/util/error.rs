@@ -6,14 +6,42 @@ use clap::ErrorKind;
 #[derive(Debug)]
 pub enum Error {
     PeatError(String),
+    ParseError(ParseError),
     IoError(io::Error),
     ClapError(clap::Error)
 }
 
+/// Columns of a line of source, counting characters from 1.
+#[derive(Debug, Copy, Clone)]
+pub struct Span {
+    pub col: usize,
+    pub len: usize,
+}
+
+/// The line of a file in which a parse error occurred.
+#[derive(Debug)]
+pub struct Location {
+    pub file_name: String,
+    pub line_number: usize,
+    pub line: String,
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Option<Span>,
+    pub location: Option<Location>,
+}
+
+impl Span {
+    pub fn new(col: usize, len: usize) -> Span { Span { col, len } }
+}
+
 impl Error {
     pub fn is_real_error(&self) -> bool {
         match self {
             Error::PeatError(_) => true,
+            Error::ParseError(_) => true,
             Error::IoError(_) => true,
             Error::ClapError(clap_error) => {
                 !matches!(clap_error.kind, ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed)
@@ -22,10 +50,70 @@ impl Error {
     }
 }
 
+impl Error {
+    /// Turns this error into a parse error at the given columns, unless it already has columns.
+    pub fn at(self, span: Span) -> Error {
+        match self {
+            Error::ParseError(ParseError { message, span: None, location }) => {
+                Error::ParseError(ParseError { message, span: Some(span), location })
+            }
+            Error::ParseError(parse_error) => Error::ParseError(parse_error),
+            error => {
+                Error::ParseError(ParseError {
+                    message: error.to_string(), span: Some(span), location: None
+                })
+            }
+        }
+    }
+
+    /// Turns this error into a parse error in the given line, unless it already has a line.
+    pub fn in_line(self, file_name: &str, line_number: usize, line: &str) -> Error {
+        let location =
+            Location { file_name: file_name.to_string(), line_number, line: line.to_string() };
+        match self {
+            Error::ParseError(ParseError { message, span, location: None }) => {
+                Error::ParseError(ParseError { message, span, location: Some(location) })
+            }
+            Error::ParseError(parse_error) => Error::ParseError(parse_error),
+            error => {
+                Error::ParseError(ParseError {
+                    message: error.to_string(), span: None, location: Some(location)
+                })
+            }
+        }
+    }
+}
+
+/// Marks the span below the line, keeping tabs so the marker lines up.
+fn caret_line(line: &str, span: Span) -> String {
+    let indent: String =
+        line.chars().take(span.col - 1).map(|ch| if ch == '\t' { '\t' } else { ' ' }).collect();
+    format!("{}{}", indent, "^".repeat(span.len.max(1)))
+}
+
+impl Display for ParseError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> result::Result<(), fmt::Error> {
+        match (&self.location, &self.span) {
+            (Some(location), Some(span)) => {
+                write!(formatter, "{}:{}:{}: {}\n{}\n{}", location.file_name,
+                       location.line_number, span.col, self.message, location.line,
+                       caret_line(&location.line, *span))
+            }
+            (Some(location), None) => {
+                write!(formatter, "{}:{}: {}\n{}", location.file_name, location.line_number,
+                       self.message, location.line)
+            }
+            (None, Some(span)) => write!(formatter, "At column {}: {}", span.col, self.message),
+            (None, None) => Display::fmt(&self.message, formatter)
+        }
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, formatter: &mut Formatter<'_>) -> result::Result<(), fmt::Error> {
         match self {
             Error::PeatError(message) => Display::fmt(message, formatter),
+            Error::ParseError(parse_error) => Display::fmt(parse_error, formatter),
             Error::IoError(io_error) => Display::fmt(io_error, formatter),
             Error::ClapError(clap_error) => Display::fmt(clap_error, formatter)
         }
@@ -0,0 +1,59 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How much peat says about what it is doing. All of it goes to stderr, so that stdout only
+/// carries the output of jobs.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Level {
+    /// Only warnings, such as failed jobs.
+    Quiet = 0,
+    Normal = 1,
+    /// Also which script each job runs, and with what.
+    Verbose = 2,
+    /// Also the content of each script.
+    Debug = 3,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Normal as u8);
+
+pub fn set_level(level: Level) { LEVEL.store(level as u8, Ordering::Relaxed) }
+
+pub fn is_enabled(level: Level) -> bool { level as u8 <= LEVEL.load(Ordering::Relaxed) }
+
+macro_rules! warning {
+    ($($arg:tt)*) => { eprintln!($($arg)*) }
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::util::log::is_enabled($crate::util::log::Level::Normal) {
+            eprintln!($($arg)*)
+        }
+    }
+}
+
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if $crate::util::log::is_enabled($crate::util::log::Level::Verbose) {
+            eprintln!($($arg)*)
+        }
+    }
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::util::log::is_enabled($crate::util::log::Level::Debug) {
+            eprintln!($($arg)*)
+        }
+    }
+}
+
+/// For messages whose level depends on the settings, such as those about each job.
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)*) => {
+        if $crate::util::log::is_enabled($level) {
+            eprintln!($($arg)*)
+        }
+    }
+}
+
+pub(crate) use {warning, info, verbose, debug, log_at};
@@ -11,12 +11,6 @@ pub(crate) struct Layer<K: Eq + Clone, V: Clone> {
     value: V,
 }
 
-struct WithValuesIterator<K: Eq + Clone, V: Clone> {
-    inner: Rc<MatryoshkaMap<K, V>>,
-    key: K,
-    value_iter: Box<dyn Iterator<Item=V>>,
-}
-
 impl<K: Eq + Clone, V: Clone> Layer<K, V> {
     pub fn new(inner: Rc<MatryoshkaMap<K, V>>, key: K, value: V) -> Layer<K, V> {
         Layer { inner, key, value}
@@ -69,13 +63,3 @@ impl<K: Eq + Clone, V: Clone> MatryoshkaMap<K, V> {
         }
     }
 }
-
-impl<K: Eq + Clone, V: Clone> Iterator for WithValuesIterator<K, V> {
-    type Item = MatryoshkaMap<K, V>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.value_iter.next().map(|value| {
-            MatryoshkaMap::Wrap(Layer { inner: self.inner.clone(), key: self.key.clone(), value })
-        })
-    }
-}
\ No newline at end of file